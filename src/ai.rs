@@ -0,0 +1,211 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, TryRecvError},
+    Arc, Mutex,
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, MakeMove};
+use crate::engine::{self, SearchLimits, Searcher};
+use crate::history::History;
+use crate::outcome::{Action, DrawOffer, GameAction};
+use crate::player::{PlayerKind, Players};
+use crate::rules::{Board, ChessClock, GameRecord, GameResult, Move};
+use crate::save::Replay;
+
+pub fn init(app: &mut App) {
+    app.init_resource::<AiSettings>()
+        .init_resource::<Ai>()
+        .add_system(think.before(BoardSystem::ApplyMoves))
+        .add_system(answer_draw_offers);
+}
+
+/// How strong the computer plays. Whichever limit is hit first ends the
+/// search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AiSettings {
+    pub depth: u32,
+    pub movetime: Duration,
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        AiSettings::from_level(5)
+    }
+}
+
+impl AiSettings {
+    pub const MAX_LEVEL: u32 = 10;
+
+    /// Strength on a scale from 1 to `MAX_LEVEL`.
+    pub fn from_level(level: u32) -> Self {
+        let level = level.clamp(1, Self::MAX_LEVEL);
+        AiSettings {
+            depth: level,
+            movetime: Duration::from_millis(300 * level as u64),
+        }
+    }
+}
+
+/// A search running on its own thread.
+struct AiTask {
+    /// The position being searched, to notice when it is no longer current.
+    board: Board,
+    result: Mutex<Receiver<Option<Move>>>,
+    search: JoinHandle<()>,
+}
+
+/// The engine playing the `PlayerKind::Computer` sides. The searcher is kept
+/// between moves so its transposition table carries over.
+pub struct Ai {
+    searcher: Arc<Mutex<Searcher>>,
+    stop: Arc<AtomicBool>,
+    task: Option<AiTask>,
+}
+
+impl Default for Ai {
+    fn default() -> Self {
+        let searcher = Searcher::new(64);
+        Ai {
+            stop: searcher.stop_flag(),
+            searcher: Arc::new(Mutex::new(searcher)),
+            task: None,
+        }
+    }
+}
+
+impl Ai {
+    /// Stops the running search, if any, and waits for its thread so the
+    /// next one doesn't find the stop flag cleared before it was seen.
+    fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = task.search.join();
+        }
+    }
+
+    fn start(
+        &mut self,
+        board: &Board,
+        record: &GameRecord,
+        settings: &AiSettings,
+        clock: Option<&ChessClock>,
+    ) {
+        // Hashes of the positions so far, so the engine can avoid repetitions
+        let mut position = record.start.clone();
+        let mut history = Vec::with_capacity(record.moves.len());
+        for recorded in &record.moves {
+            history.push(engine::hash(&position));
+            position.make_move(recorded.mv);
+        }
+
+        self.stop.store(false, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel();
+        let searcher = self.searcher.clone();
+        let searched = board.clone();
+        // Don't spend more than a small share of the time left on the clock
+        let movetime = match clock {
+            Some(clock) => settings
+                .movetime
+                .min(clock.remaining(board.side_to_move) / 30),
+            None => settings.movetime,
+        };
+        let limits = SearchLimits {
+            depth: Some(settings.depth),
+            movetime: Some(movetime),
+            nodes: None,
+        };
+        let search = thread::spawn(move || {
+            let mut searcher = searcher.lock().unwrap();
+            searcher.set_history(history);
+            let best = searcher.search(&searched, limits, |info| {
+                debug!(
+                    "depth {} score {} nodes {}",
+                    info.depth, info.score, info.nodes
+                );
+            });
+            // The receiver is gone if the game moved on in the meantime
+            let _ = sender.send(best);
+        });
+
+        self.task = Some(AiTask {
+            board: board.clone(),
+            result: Mutex::new(receiver),
+            search,
+        });
+    }
+}
+
+/// Starts a background search when the computer is to move and plays its
+/// result once it arrives, so the frame loop never waits for the engine.
+fn think(
+    mut ai: ResMut<Ai>,
+    board: Res<Board>,
+    record: Res<GameRecord>,
+    players: Res<Players>,
+    settings: Res<AiSettings>,
+    clock: Option<Res<ChessClock>>,
+    replay: Option<Res<Replay>>,
+    history: Res<History>,
+    mut make_move: EventWriter<MakeMove>,
+) {
+    let ai = &mut *ai;
+    if let Some(task) = &ai.task {
+        if task.board != *board {
+            // Position changed under the search, e.g. by loading a game
+            ai.stop();
+        } else {
+            let result = task.result.lock().unwrap().try_recv();
+            match result {
+                Ok(Some(mv)) => {
+                    make_move.send(MakeMove(mv));
+                    ai.task = None;
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Ok(None) | Err(TryRecvError::Disconnected) => ai.task = None,
+            }
+        }
+    }
+
+    if players.get(board.side_to_move) == PlayerKind::Computer
+        && record.result == GameResult::Ongoing
+        && replay.is_none()
+        && !history.browsing()
+        && !board.legal_moves().is_empty()
+    {
+        ai.start(&board, &record, &settings, clock.as_deref());
+    }
+}
+
+/// Engines answer draw offers right away. The built-in one accepts unless it
+/// thinks it stands better, external engines always decline. Humans, here or
+/// across the network, answer themselves.
+fn answer_draw_offers(
+    board: Res<Board>,
+    players: Res<Players>,
+    offer: Res<DrawOffer>,
+    mut actions: EventWriter<GameAction>,
+) {
+    let color = match offer.0 {
+        Some(offered_by) if offer.is_changed() => offered_by.opponent(),
+        _ => return,
+    };
+    let action = match players.get(color) {
+        PlayerKind::Human | PlayerKind::Remote => return,
+        PlayerKind::Computer => {
+            let mut own_view = board.clone();
+            own_view.side_to_move = color;
+            if engine::evaluate(&own_view) <= 0 {
+                Action::AcceptDraw
+            } else {
+                Action::DeclineDraw
+            }
+        }
+        PlayerKind::External => Action::DeclineDraw,
+    };
+    actions.send(GameAction { color, action });
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, PieceCaptured, PieceMoved};
+use crate::piece::*;
+use crate::rules::{Board, BoardSize};
+
+pub fn init(app: &mut App) {
+    app.init_resource::<AnimationSettings>()
+        .add_system(start_animations.after(BoardSystem::SyncPieces))
+        .add_system(animate_pieces);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseInOutSine,
+    EaseOutQuad,
+    EaseInOutCubic,
+}
+
+impl Easing {
+    /// Maps linear progress `t` in `0..=1` onto the curve.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOutSine => -((std::f32::consts::PI * t).cos() - 1.0) / 2.0,
+            Easing::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+pub struct AnimationSettings {
+    /// Seconds a piece takes to reach its new square.
+    pub move_duration: f32,
+    /// Seconds a captured piece takes to leave the board.
+    pub capture_duration: f32,
+    pub easing: Easing,
+    /// Peak height of the arc knights jump along.
+    pub knight_arc_height: f32,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        AnimationSettings {
+            move_duration: 0.35,
+            capture_duration: 0.5,
+            easing: Easing::EaseInOutCubic,
+            knight_arc_height: 0.8,
+        }
+    }
+}
+
+/// Moves the entity along an eased, optionally arced path.
+#[derive(Component, Debug, Clone)]
+pub struct PieceAnimation {
+    from: Vec3,
+    to: Vec3,
+    arc_height: f32,
+    elapsed: f32,
+    duration: f32,
+    easing: Easing,
+    /// Despawn the entity once it arrives, used for captured pieces.
+    despawn: bool,
+}
+
+impl PieceAnimation {
+    /// Position at the current progress.
+    pub fn translation(&self) -> Vec3 {
+        let t = if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        };
+        let eased = self.easing.apply(t);
+        let mut translation = self.from.lerp(self.to, eased);
+        translation.y += self.arc_height * 4.0 * eased * (1.0 - eased);
+        translation
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Off-board spot a captured piece leaves the board towards, past the edge
+/// of the board of `size` closest to it.
+fn capture_destination(from: Vec3, size: BoardSize) -> Vec3 {
+    let center = board_center(size);
+    let z = if from.z < center.z {
+        -1.5
+    } else {
+        size.files as f32 + 0.5
+    };
+    Vec3::new(from.x, -0.5, z)
+}
+
+fn start_animations(
+    mut commands: Commands,
+    settings: Res<AnimationSettings>,
+    board: Res<Board>,
+    mut moved: EventReader<PieceMoved>,
+    mut captured: EventReader<PieceCaptured>,
+    pieces: Query<(&Transform, Option<&Piece>)>,
+) {
+    for &PieceMoved { entity, from, to } in moved.iter() {
+        // Not there yet when the piece was only just spawned, as on promotion
+        let (from, arc_height) = match pieces.get(entity) {
+            Ok((transform, piece)) => {
+                let arc_height = match piece {
+                    Some(piece) if piece.ptype == PieceType::Knight => settings.knight_arc_height,
+                    _ => 0.0,
+                };
+                (transform.translation, arc_height)
+            }
+            Err(_) => (square_translation(from), 0.0),
+        };
+        commands.entity(entity).insert(PieceAnimation {
+            from,
+            to: square_translation(to),
+            arc_height,
+            elapsed: 0.0,
+            duration: settings.move_duration,
+            easing: settings.easing,
+            despawn: false,
+        });
+    }
+
+    for &PieceCaptured { entity } in captured.iter() {
+        if let Ok((transform, _)) = pieces.get(entity) {
+            commands.entity(entity).insert(PieceAnimation {
+                from: transform.translation,
+                to: capture_destination(transform.translation, board.size()),
+                arc_height: 0.0,
+                elapsed: 0.0,
+                duration: settings.capture_duration,
+                easing: settings.easing,
+                despawn: true,
+            });
+        }
+    }
+}
+
+fn animate_pieces(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animations: Query<(Entity, &mut PieceAnimation, &mut Transform)>,
+) {
+    for (entity, mut animation, mut transform) in animations.iter_mut() {
+        animation.elapsed += time.delta_seconds();
+        transform.translation = animation.translation();
+
+        if animation.finished() {
+            if animation.despawn {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                commands.entity(entity).remove::<PieceAnimation>();
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! The built-in engine as a headless UCI engine, for use in chess GUIs and
+//! tournament managers.
+
+use std::io::{self, BufRead};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::thread::{self, JoinHandle};
+
+use bevy_chess::engine::{self, Searcher};
+use bevy_chess::rules::{Board, Move};
+use bevy_chess::uci::{self, GoParams, GuiCommand, Info};
+
+const DEFAULT_HASH_MB: usize = 64;
+
+struct Engine {
+    searcher: Arc<Mutex<Searcher>>,
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<()>>,
+    start: Board,
+    moves: Vec<Move>,
+    /// The `UCI_Chess960` option, castling is written king takes rook.
+    chess960: bool,
+}
+
+impl Engine {
+    fn new(hash_mb: usize) -> Self {
+        let searcher = Searcher::new(hash_mb);
+        Engine {
+            stop: searcher.stop_flag(),
+            searcher: Arc::new(Mutex::new(searcher)),
+            search: None,
+            start: Board::start(),
+            moves: Vec::new(),
+            chess960: false,
+        }
+    }
+
+    /// Stops the running search, if any, and waits for its `bestmove`.
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = search.join();
+        }
+    }
+
+    fn go(&mut self, params: GoParams) {
+        self.stop();
+
+        let mut board = self.start.clone();
+        let mut history = Vec::with_capacity(self.moves.len());
+        for &mv in &self.moves {
+            history.push(engine::hash(&board));
+            board.make_move(mv);
+        }
+        let limits = params.limits(board.side_to_move);
+        self.stop.store(false, Ordering::Relaxed);
+
+        let searcher = self.searcher.clone();
+        self.search = Some(thread::spawn(move || {
+            let mut searcher = searcher.lock().unwrap();
+            searcher.set_history(history);
+            let best = searcher.search(&board, limits, |info| println!("{}", Info::from(info)));
+            match best {
+                Some(mv) => println!("bestmove {}", uci::move_text(&board, mv)),
+                None => println!("bestmove 0000"),
+            }
+        }));
+    }
+
+    fn handle(&mut self, command: GuiCommand) -> bool {
+        match command {
+            GuiCommand::Uci => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!("id author {}", env!("CARGO_PKG_NAME"));
+                println!(
+                    "option name Hash type spin default {} min 1 max 4096",
+                    DEFAULT_HASH_MB
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            GuiCommand::IsReady => println!("readyok"),
+            GuiCommand::SetOption { name, value } => {
+                if name.eq_ignore_ascii_case("hash") {
+                    match value.and_then(|v| v.parse().ok()) {
+                        Some(mb) => {
+                            self.stop();
+                            let chess960 = self.chess960;
+                            *self = Engine::new(mb);
+                            self.chess960 = chess960;
+                        }
+                        None => println!("info string Hash expects a size in MB"),
+                    }
+                } else if name.eq_ignore_ascii_case("uci_chess960") {
+                    self.chess960 = matches!(value, Some(v) if v.eq_ignore_ascii_case("true"));
+                }
+            }
+            GuiCommand::UciNewGame => {
+                self.stop();
+                self.searcher.lock().unwrap().clear();
+            }
+            GuiCommand::Position { start, moves } => {
+                self.stop();
+                self.start = *start;
+                self.start.chess960 |= self.chess960;
+                self.moves = moves;
+            }
+            GuiCommand::Go(params) => self.go(params),
+            GuiCommand::Stop => self.stop(),
+            GuiCommand::Quit => {
+                self.stop();
+                return false;
+            }
+            GuiCommand::Debug(_) | GuiCommand::PonderHit => {}
+        }
+        true
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new(DEFAULT_HASH_MB);
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let keep_running = match GuiCommand::parse(&line) {
+            Ok(command) => engine.handle(command),
+            Err(err) => {
+                println!("info string {}", err);
+                true
+            }
+        };
+        if !keep_running {
+            break;
+        }
+    }
+    engine.stop();
+}
@@ -0,0 +1,360 @@
+use bevy::prelude::*;
+
+use crate::history::History;
+use crate::piece::*;
+use crate::rules::{
+    Board, ChessClock, GameRecord, GameResult, Move, PieceChange, PieceColor, Square,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub enum BoardSystem {
+    ApplyMoves,
+    SyncPieces,
+}
+
+/// Marks one of the board square entities spawned by `create_board`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardSquare(pub Square);
+
+/// Request to play a move on the logical board. Illegal moves are ignored.
+pub struct MakeMove(pub Move);
+
+/// Sent by `apply_moves` after a move was played on the `Board`.
+pub struct MovePlayed {
+    pub mv: Move,
+    pub color: PieceColor,
+    /// Index of the move in `GameRecord::moves`.
+    pub ply: usize,
+}
+
+/// How the pieces on the `Board` just changed, from a move being played or
+/// taken back. `sync_pieces` carries them out on the entities.
+pub struct PieceChanges(pub Vec<PieceChange>);
+
+/// Sent by `sync_pieces` when a piece entity has to go to another square.
+pub struct PieceMoved {
+    pub entity: Entity,
+    /// Square the piece left. Where it is shown counts if it has been
+    /// spawned already, a promoted piece is only spawned with this event.
+    pub from: Square,
+    pub to: Square,
+}
+
+/// Sent by `sync_pieces` when a piece entity left the board. It no longer
+/// has a `Piece` component and is up to the receiver to despawn.
+pub struct PieceCaptured {
+    pub entity: Entity,
+}
+
+pub fn init(app: &mut App) {
+    app.init_resource::<Board>()
+        .init_resource::<GameRecord>()
+        .add_event::<MakeMove>()
+        .add_event::<MovePlayed>()
+        .add_event::<PieceChanges>()
+        .add_event::<PieceMoved>()
+        .add_event::<PieceCaptured>()
+        .add_system(apply_moves.label(BoardSystem::ApplyMoves))
+        .add_system(
+            sync_pieces
+                .label(BoardSystem::SyncPieces)
+                .after(BoardSystem::ApplyMoves),
+        )
+        .add_system(log_fen);
+}
+
+/// Plays requested moves on the `Board` and records them in the `GameRecord`
+/// and the `History`.
+fn apply_moves(
+    mut events: EventReader<MakeMove>,
+    mut board: ResMut<Board>,
+    mut record: ResMut<GameRecord>,
+    mut history: ResMut<History>,
+    mut clock: Option<ResMut<ChessClock>>,
+    mut played: EventWriter<MovePlayed>,
+    mut changes: EventWriter<PieceChanges>,
+) {
+    for &MakeMove(mv) in events.iter() {
+        if record.result != GameResult::Ongoing || !board.is_legal(mv) {
+            warn!("ignoring illegal move {}", mv);
+            continue;
+        }
+        // The game goes on from an earlier position, and so do the clocks
+        if history.browsing() {
+            if let Some(clock) = &mut clock {
+                clock.restore(&record);
+            }
+        }
+        let color = board.side_to_move;
+        record.push(&board, mv);
+        changes.send(PieceChanges(board.piece_changes(mv)));
+        history.push(mv, board.make_reversible_move(mv));
+        played.send(MovePlayed {
+            mv,
+            color,
+            ply: record.moves.len() - 1,
+        });
+    }
+}
+
+/// Brings the piece entities in line with the logical `Board`, which is the
+/// single source of truth for the position.
+///
+/// `PieceChanges` are replayed on the entities, so castling moves the king
+/// and the rook, and en passant takes the pawn beside the destination rather
+/// than on it. Whatever still differs, e.g. after loading a position, is
+/// reconciled afterwards: an entity is moved to a square that gained a piece
+/// of the same type and color. If there is none, it is replaced when its
+/// color gained a piece of another type, and captured otherwise. Remaining
+/// unmatched pieces are spawned. A promoted piece is respawned as its new
+/// type right away and moves from the square the pawn left, and back when
+/// the promotion is taken back. Moves and captures are only announced, the
+/// `animation` module carries them out.
+pub fn sync_pieces(
+    mut commands: Commands,
+    board: Res<Board>,
+    assets: Res<PieceAssets>,
+    mut changes: EventReader<PieceChanges>,
+    mut pieces: Query<(Entity, &mut Piece)>,
+    mut moved: EventWriter<PieceMoved>,
+    mut captured: EventWriter<PieceCaptured>,
+) {
+    // Entities whose removal is still queued in `commands`, and pieces
+    // whose entities are still to be spawned by them
+    let mut gone = Vec::new();
+    let mut spawned = Vec::new();
+
+    let at = |pieces: &Query<(Entity, &mut Piece)>, square: Square, gone: &[Entity]| {
+        pieces
+            .iter()
+            .find(|(entity, piece)| piece.square == square && !gone.contains(entity))
+            .map(|(entity, _)| entity)
+    };
+    for PieceChanges(changes) in changes.iter() {
+        for &change in changes {
+            if let PieceChange::Removed(square) = change {
+                if let Some(entity) = at(&pieces, square, &gone) {
+                    commands.entity(entity).remove::<Piece>();
+                    captured.send(PieceCaptured { entity });
+                    gone.push(entity);
+                }
+            }
+        }
+
+        // Looked up before any of them moves, castling may swap king and rook
+        let movers: Vec<(Entity, Square, Square)> = changes
+            .iter()
+            .filter_map(|&change| match change {
+                PieceChange::Moved { from, to } => {
+                    at(&pieces, from, &gone).map(|entity| (entity, from, to))
+                }
+                _ => None,
+            })
+            .collect();
+        let replaced = |square: Square| {
+            changes.iter().any(
+                |&change| matches!(change, PieceChange::Replaced { square: s, .. } if s == square),
+            )
+        };
+        for &(entity, from, to) in &movers {
+            pieces.get_mut(entity).unwrap().1.square = to;
+            // A piece about to be replaced is despawned, its successor moves
+            if !replaced(to) {
+                moved.send(PieceMoved { entity, from, to });
+            }
+        }
+
+        for &change in changes {
+            if let PieceChange::Replaced { square, ptype } = change {
+                let entity = match at(&pieces, square, &gone) {
+                    Some(entity) => entity,
+                    None => continue,
+                };
+                let color = pieces.get(entity).unwrap().1.color;
+                commands.entity(entity).despawn_recursive();
+                gone.push(entity);
+
+                let piece = Piece {
+                    ptype,
+                    color,
+                    square,
+                };
+                let successor = Piece::spawn(&mut commands, piece, &assets);
+                spawned.push(piece);
+                if let Some(&(_, from, _)) = movers.iter().find(|&&(mover, ..)| mover == entity) {
+                    commands
+                        .entity(successor)
+                        .insert(Transform::from_translation(square_translation(from)));
+                    moved.send(PieceMoved {
+                        entity: successor,
+                        from,
+                        to: square,
+                    });
+                }
+            }
+        }
+    }
+
+    if !board.is_changed() {
+        return;
+    }
+
+    let mut unclaimed: Vec<(Square, PieceType, PieceColor)> = board
+        .pieces()
+        .filter(|&(square, ptype, color)| {
+            !spawned.contains(&Piece {
+                ptype,
+                color,
+                square,
+            })
+        })
+        .collect();
+    let mut stale = Vec::new();
+    for (entity, piece) in pieces.iter() {
+        if gone.contains(&entity) {
+            continue;
+        }
+        match unclaimed.iter().position(|&(square, ptype, color)| {
+            square == piece.square && ptype == piece.ptype && color == piece.color
+        }) {
+            Some(i) => {
+                unclaimed.swap_remove(i);
+            }
+            None => stale.push(entity),
+        }
+    }
+
+    for entity in stale {
+        let (_, mut piece) = pieces.get_mut(entity).unwrap();
+        match unclaimed
+            .iter()
+            .position(|&(_, ptype, color)| ptype == piece.ptype && color == piece.color)
+        {
+            Some(i) => {
+                let (square, _, _) = unclaimed.swap_remove(i);
+                let from = piece.square;
+                piece.square = square;
+                moved.send(PieceMoved {
+                    entity,
+                    from,
+                    to: square,
+                });
+            }
+            None if unclaimed.iter().any(|&(_, _, color)| color == piece.color) => {
+                commands.entity(entity).despawn_recursive();
+            }
+            None => {
+                commands.entity(entity).remove::<Piece>();
+                captured.send(PieceCaptured { entity });
+            }
+        }
+    }
+
+    for (square, ptype, color) in unclaimed {
+        let piece = Piece {
+            ptype,
+            color,
+            square,
+        };
+        Piece::spawn(&mut commands, piece, &assets);
+    }
+}
+
+/// Print the current position as FEN with F, e.g. to reproduce a bug.
+fn log_fen(input: Res<Input<KeyCode>>, board: Res<Board>) {
+    if input.just_pressed(KeyCode::F) {
+        info!("{}", board.to_fen());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::{self, PieceAnimation};
+    use crate::rules::MoveKind;
+    use bevy::app::Events;
+    use std::collections::HashMap;
+
+    fn app(board: Board) -> App {
+        let mut app = App::new();
+        app.insert_resource(board)
+            .insert_resource(Time::default())
+            .insert_resource(PieceAssets {
+                set: Handle::default(),
+                models: HashMap::new(),
+                white_material: Handle::default(),
+                black_material: Handle::default(),
+                fairy_looks: HashMap::new(),
+                fairy_materials: HashMap::new(),
+            })
+            .add_event::<PieceChanges>()
+            .add_event::<PieceMoved>()
+            .add_event::<PieceCaptured>()
+            .add_system(sync_pieces.label(BoardSystem::SyncPieces));
+        animation::init(&mut app);
+        app
+    }
+
+    /// Carries out `changes` after `update` brought the `Board` to the
+    /// position they lead to.
+    fn sync(app: &mut App, changes: Vec<PieceChange>, update: impl FnOnce(&mut Board)) {
+        update(&mut app.world.get_resource_mut::<Board>().unwrap());
+        app.world
+            .get_resource_mut::<Events<PieceChanges>>()
+            .unwrap()
+            .send(PieceChanges(changes));
+        app.update();
+    }
+
+    /// The pieces on the board and whether they are being animated.
+    fn pieces(app: &mut App) -> Vec<(Piece, bool)> {
+        app.world
+            .query::<(&Piece, Option<&PieceAnimation>)>()
+            .iter(&app.world)
+            .map(|(&piece, animation)| (piece, animation.is_some()))
+            .collect()
+    }
+
+    #[test]
+    fn promote_and_take_back() {
+        let board = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut app = app(board.clone());
+        app.update();
+        assert_eq!(pieces(&mut app).len(), 4);
+
+        let a7 = Square::parse("a7").unwrap();
+        let b8 = Square::parse("b8").unwrap();
+        let mv = Move::new(a7, b8, MoveKind::PromotionCapture(PieceType::Queen));
+        let mut undo = None;
+        sync(&mut app, board.piece_changes(mv), |board| {
+            undo = Some(board.make_reversible_move(mv));
+        });
+        let promoted = Piece {
+            ptype: PieceType::Queen,
+            color: PieceColor::White,
+            square: b8,
+        };
+        let after = pieces(&mut app);
+        assert_eq!(after.len(), 3);
+        assert!(after.contains(&(promoted, true)));
+
+        let undo = undo.unwrap();
+        sync(&mut app, board.unmake_piece_changes(mv), |board| {
+            board.unmake_move(&undo);
+        });
+        let pawn = Piece {
+            ptype: PieceType::Pawn,
+            color: PieceColor::White,
+            square: a7,
+        };
+        let rook = Piece {
+            ptype: PieceType::Rook,
+            color: PieceColor::Black,
+            square: b8,
+        };
+        let before = pieces(&mut app);
+        assert_eq!(before.len(), 4);
+        assert!(before.contains(&(pawn, true)));
+        assert!(before.iter().any(|&(piece, _)| piece == rook));
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, MovePlayed};
+use crate::history::History;
+use crate::outcome::{self, GameOver};
+use crate::rules::{Board, ChessClock, GameRecord, Outcome, Termination};
+use crate::save::Replay;
+
+pub fn init(app: &mut App) {
+    app.add_system(run_clock.label(ClockSystem).after(BoardSystem::ApplyMoves));
+}
+
+/// Labels `run_clock`, after which the clock has been pressed for the moves
+/// of the frame.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct ClockSystem;
+
+/// Runs the `ChessClock`, if the game has one, for the side to move and
+/// presses it after each move. A fallen flag loses the game, unless the
+/// opponent has nothing left to mate with.
+fn run_clock(
+    time: Res<Time>,
+    clock: Option<ResMut<ChessClock>>,
+    board: Res<Board>,
+    mut record: ResMut<GameRecord>,
+    replay: Option<Res<Replay>>,
+    history: Res<History>,
+    mut played: EventReader<MovePlayed>,
+    mut game_over: EventWriter<GameOver>,
+) {
+    let mut clock = match clock {
+        Some(clock) => clock,
+        None => return,
+    };
+
+    for event in played.iter() {
+        clock.press(event.color);
+        if let Some(recorded) = record.moves.get_mut(event.ply) {
+            recorded.clock = Some(clock.remaining(event.color));
+        }
+    }
+
+    // A replayed game restores its clocks once it is through. While an
+    // earlier position is shown, the game goes on at its end.
+    if history.game_over(&record) || replay.is_some() {
+        return;
+    }
+    let side_to_move = history.side_to_move(&board);
+    if clock.tick(side_to_move, time.delta()) {
+        let opponent = side_to_move.opponent();
+        let end = history.whole_game(&record);
+        let outcome = if end.board_at(end.moves.len()).has_mating_material(opponent) {
+            Outcome::win(opponent, Termination::TimeForfeit)
+        } else {
+            Outcome::draw(Termination::TimeForfeit)
+        };
+        outcome::finish(&mut record, outcome, &mut game_over);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::rules::{Board, BoardSize, PieceColor, PieceType, Square, VariantKind, DROP_PIECES};
+
+/// Worth of `ptype` in centipawns, fairy pieces as defined on `board`.
+pub fn piece_value(board: &Board, ptype: PieceType) -> i32 {
+    match ptype {
+        PieceType::King => 0,
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Rook => 500,
+        PieceType::Bishop => 330,
+        PieceType::Queen => 900,
+        PieceType::Fairy(_) => board.fairy(ptype).map_or(0, |fairy| fairy.value),
+    }
+}
+
+// Piece-square tables from White's point of view, written with rank 8 at
+// the top so they read like a diagram.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// Positional bonus for `ptype` of `color` standing on `square`.
+pub fn piece_square_value(
+    ptype: PieceType,
+    color: PieceColor,
+    square: Square,
+    endgame: bool,
+) -> i32 {
+    let row = match color {
+        PieceColor::White => 7 - square.rank as usize,
+        PieceColor::Black => square.rank as usize,
+    };
+    let index = row * 8 + square.file as usize;
+    match ptype {
+        PieceType::King if endgame => KING_ENDGAME_TABLE[index],
+        PieceType::King => KING_MIDDLEGAME_TABLE[index],
+        PieceType::Pawn => PAWN_TABLE[index],
+        PieceType::Knight => KNIGHT_TABLE[index],
+        PieceType::Rook => ROOK_TABLE[index],
+        PieceType::Bishop => BISHOP_TABLE[index],
+        PieceType::Queen => QUEEN_TABLE[index],
+        // Like knights, most fairy pieces are best in the centre
+        PieceType::Fairy(_) => KNIGHT_TABLE[index],
+    }
+}
+
+/// `square` moved onto the 8x8 piece-square tables, stretched or squeezed
+/// for boards of other sizes.
+fn table_square(size: BoardSize, square: Square) -> Square {
+    let scale = |x: u8, n: u8| if n > 1 { x * 7 / (n - 1) } else { 0 };
+    Square::new(
+        scale(square.file, size.files),
+        scale(square.rank, size.ranks),
+    )
+}
+
+/// Static evaluation in centipawns from the side to move's point of view.
+pub fn evaluate(board: &Board) -> i32 {
+    // Endgame once the queens are off or little material is left
+    let non_pawn_material: i32 = board
+        .pieces()
+        .filter(|&(_, ptype, _)| ptype != PieceType::Pawn && ptype != PieceType::King)
+        .map(|(_, ptype, _)| piece_value(board, ptype))
+        .sum();
+    let queens = board
+        .pieces()
+        .filter(|&(_, ptype, _)| ptype == PieceType::Queen)
+        .count();
+    let endgame = queens == 0 || non_pawn_material <= 2 * 1300;
+
+    let mut score = 0;
+    for (square, ptype, color) in board.pieces() {
+        let table_square = table_square(board.size(), square);
+        let value =
+            piece_value(board, ptype) + piece_square_value(ptype, color, table_square, endgame);
+        if color == board.side_to_move {
+            score += value;
+        } else {
+            score -= value;
+        }
+    }
+    // Pieces in hand count like those on the board
+    for ptype in DROP_PIECES {
+        let own = board.variant_state.pocket(board.side_to_move, ptype) as i32;
+        let other = board
+            .variant_state
+            .pocket(board.side_to_move.opponent(), ptype) as i32;
+        score += piece_value(board, ptype) * (own - other);
+    }
+    // Giving material away is the point of Antichess
+    if board.variant == VariantKind::Antichess {
+        -score
+    } else {
+        score
+    }
+}
@@ -0,0 +1,12 @@
+//! Built-in chess engine on top of `rules`: evaluation, hashing and an
+//! alpha-beta search. Like `rules` it does not depend on Bevy.
+
+mod eval;
+mod search;
+mod tt;
+mod zobrist;
+
+pub use eval::*;
+pub use search::*;
+pub use tt::*;
+pub use zobrist::*;
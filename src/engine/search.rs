@@ -0,0 +1,418 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+use super::*;
+use crate::rules::{Board, GameResult, Move, MoveKind, Outcome, PieceType, VariantKind};
+
+pub const MATE_SCORE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+const MAX_PLY: i32 = 128;
+
+/// When to stop searching. Unset limits are unbounded, but a search without
+/// any limit only ends through the stop flag.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> Self {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+}
+
+/// Progress report after each completed iteration.
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// Centipawns from the side to move's point of view.
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    /// Moves until mate, negative when the side to move gets mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score.abs() < MATE_SCORE - MAX_PLY {
+            return None;
+        }
+        let plies = MATE_SCORE - self.score.abs();
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+/// Iterative deepening alpha-beta search with a transposition table.
+pub struct Searcher {
+    tt: TranspositionTable,
+    stop: Arc<AtomicBool>,
+    /// Hashes of the positions played before the root, for repetitions.
+    history: Vec<u64>,
+    path: Vec<u64>,
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    aborted: bool,
+    root_best: Option<Move>,
+}
+
+impl Searcher {
+    pub fn new(tt_size_mb: usize) -> Self {
+        Searcher {
+            tt: TranspositionTable::new(tt_size_mb),
+            stop: Arc::new(AtomicBool::new(false)),
+            history: Vec::new(),
+            path: Vec::new(),
+            limits: SearchLimits::default(),
+            start: Instant::now(),
+            nodes: 0,
+            aborted: false,
+            root_best: None,
+        }
+    }
+
+    /// Setting the returned flag makes a running search return its best
+    /// move so far. Searches don't clear it, that is up to the caller before
+    /// starting the next one.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn set_history(&mut self, history: Vec<u64>) {
+        self.history = history;
+    }
+
+    /// Forgets everything learned, e.g. for a new game.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.history.clear();
+    }
+
+    /// Searches `board` and returns the best move, or `None` if there are no
+    /// legal moves. `on_info` is called after every completed depth.
+    pub fn search(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> Option<Move> {
+        self.limits = limits;
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.aborted = false;
+        self.path.clear();
+
+        let legal = board.legal_moves();
+        let mut best = *legal.first()?;
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32).max(1);
+
+        for depth in 1..=max_depth {
+            self.root_best = None;
+            let score = self.negamax(board, depth as i32, -INFINITY, INFINITY, 0);
+            if self.aborted {
+                // A partial iteration still searched its first move fully
+                if let Some(mv) = self.root_best {
+                    best = mv;
+                }
+                break;
+            }
+            if let Some(mv) = self.root_best {
+                best = mv;
+            }
+
+            on_info(&SearchInfo {
+                depth,
+                score,
+                nodes: self.nodes,
+                time: self.start.elapsed(),
+                pv: self.principal_variation(board, depth),
+            });
+
+            if score.abs() >= MATE_SCORE - depth as i32 {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        if self.stop.load(Ordering::Relaxed) {
+            self.aborted = true;
+        } else if self.nodes & 1023 == 0 {
+            let out_of_time = matches!(self.limits.movetime, Some(t) if self.start.elapsed() >= t);
+            let out_of_nodes = matches!(self.limits.nodes, Some(n) if self.nodes >= n);
+            self.aborted = out_of_time || out_of_nodes;
+        }
+        self.aborted
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        mut depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let hash = hash(board);
+        if ply > 0 && (board.halfmove_clock >= 100 || self.is_repetition(hash)) {
+            return 0;
+        }
+        if ply > 0 {
+            if let Some(outcome) = board.variant.rules().decisive_outcome(board) {
+                return outcome_score(board, outcome, ply);
+            }
+        }
+
+        let in_check = board.in_check(board.side_to_move);
+        if in_check && ply < MAX_PLY {
+            depth += 1;
+        }
+        if depth <= 0 || ply >= MAX_PLY {
+            return self.quiescence(board, alpha, beta, ply);
+        }
+
+        let entry = self.tt.get(hash);
+        if let Some(entry) = entry {
+            if ply > 0 && entry.depth >= depth as u32 {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            let outcome = board.variant.rules().no_moves_outcome(board);
+            return outcome_score(board, outcome, ply);
+        }
+        order_moves(board, &mut moves, entry.and_then(|entry| entry.best));
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        self.path.push(hash);
+        for mv in moves {
+            let mut next = board.clone();
+            next.make_move(mv);
+            let score = -self.negamax(&next, depth - 1, -beta, -alpha, ply + 1);
+            if self.aborted {
+                self.path.pop();
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if ply == 0 {
+                    self.root_best = Some(mv);
+                }
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        self.path.pop();
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(Entry {
+            hash,
+            depth: depth as u32,
+            score: score_to_tt(best_score, ply),
+            bound,
+            best: best_move,
+        });
+        best_score
+    }
+
+    /// Searches captures and promotions only, so the static evaluation is
+    /// never taken in the middle of an exchange.
+    fn quiescence(&mut self, board: &Board, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if board.variant != VariantKind::Standard {
+            return self.variant_quiescence(board, alpha, beta, ply);
+        }
+
+        let stand_pat = evaluate(board);
+        if stand_pat >= beta || ply >= 2 * MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let color = board.side_to_move;
+        let mut moves: Vec<Move> = board
+            .pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| mv.is_capture() || mv.promotion().is_some())
+            .collect();
+        order_moves(board, &mut moves, None);
+
+        for mv in moves {
+            let mut next = board.clone();
+            next.make_move(mv);
+            if next.in_check(color) {
+                continue;
+            }
+            let score = -self.quiescence(&next, -beta, -alpha, ply + 1);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// `quiescence` for variants, where pseudo-legal moves that keep the
+    /// king safe are not necessarily the legal ones and a capture may end
+    /// the game.
+    fn variant_quiescence(&mut self, board: &Board, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        if let Some(outcome) = board.variant.rules().decisive_outcome(board) {
+            return outcome_score(board, outcome, ply);
+        }
+
+        let stand_pat = evaluate(board);
+        if stand_pat >= beta || ply >= 2 * MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<Move> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.is_capture() || mv.promotion().is_some())
+            .collect();
+        order_moves(board, &mut moves, None);
+
+        for mv in moves {
+            let mut next = board.clone();
+            next.make_move(mv);
+            let score = -self.quiescence(&next, -beta, -alpha, ply + 1);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    fn is_repetition(&self, hash: u64) -> bool {
+        self.path.contains(&hash) || self.history.contains(&hash)
+    }
+
+    fn principal_variation(&self, board: &Board, depth: u32) -> Vec<Move> {
+        let mut pv = Vec::new();
+        let mut board = board.clone();
+        while pv.len() < depth as usize {
+            let mv = match self.tt.get(hash(&board)).and_then(|entry| entry.best) {
+                Some(mv) if board.is_legal(mv) => mv,
+                _ => break,
+            };
+            pv.push(mv);
+            board.make_move(mv);
+        }
+        pv
+    }
+}
+
+/// Score of a finished game from the side to move's point of view, with
+/// quicker wins scored higher like mates.
+fn outcome_score(board: &Board, outcome: Outcome, ply: i32) -> i32 {
+    if outcome.result == GameResult::Draw {
+        0
+    } else if outcome.result == GameResult::win_for(board.side_to_move) {
+        MATE_SCORE - ply
+    } else {
+        -MATE_SCORE + ply
+    }
+}
+
+/// Mate scores are stored relative to the node instead of the root so they
+/// stay valid when the position is reached at a different ply.
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE_SCORE - 2 * MAX_PLY {
+        score + ply
+    } else if score <= -MATE_SCORE + 2 * MAX_PLY {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE_SCORE - 2 * MAX_PLY {
+        score - ply
+    } else if score <= -MATE_SCORE + 2 * MAX_PLY {
+        score + ply
+    } else {
+        score
+    }
+}
+
+/// Hash move first, then captures by most valuable victim and least
+/// valuable attacker, then promotions, then quiet moves.
+fn order_moves(board: &Board, moves: &mut [Move], hash_move: Option<Move>) {
+    moves.sort_by_cached_key(|&mv| {
+        let score = if Some(mv) == hash_move {
+            1_000_000
+        } else if mv.is_capture() {
+            let victim = match mv.kind {
+                MoveKind::EnPassant => PieceType::Pawn,
+                _ => board.get(mv.to).map_or(PieceType::Pawn, |(ptype, _)| ptype),
+            };
+            let attacker = board
+                .get(mv.from)
+                .map_or(PieceType::Pawn, |(ptype, _)| ptype);
+            10_000 + 10 * piece_value(board, victim) - piece_value(board, attacker)
+        } else if let Some(ptype) = mv.promotion() {
+            5_000 + piece_value(board, ptype)
+        } else {
+            0
+        };
+        -score
+    });
+}
@@ -0,0 +1,54 @@
+use crate::rules::Move;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this high (fail high).
+    Lower,
+    /// The score is at most this high (fail low).
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub hash: u64,
+    pub depth: u32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best: Option<Move>,
+}
+
+/// Fixed size hash table of search results, replacing on collision.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let len = (size_mb * 1024 * 1024 / std::mem::size_of::<Option<Entry>>()).max(1);
+        TranspositionTable {
+            entries: vec![None; len],
+        }
+    }
+
+    pub fn get(&self, hash: u64) -> Option<Entry> {
+        self.entries[self.index(hash)].filter(|entry| entry.hash == hash)
+    }
+
+    pub fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.hash);
+        match self.entries[index] {
+            // Keep deeper results for the same position
+            Some(old) if old.hash == entry.hash && old.depth > entry.depth => {}
+            _ => self.entries[index] = Some(entry),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.entries.len() as u64) as usize
+    }
+}
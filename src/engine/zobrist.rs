@@ -0,0 +1,122 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::rules::{
+    Board, PieceColor, PieceType, VariantState, MAX_FAIRY_PIECES, MAX_FILES, MAX_RANKS,
+};
+
+const SQUARES: usize = MAX_FILES as usize * MAX_RANKS as usize;
+
+/// The six standard pieces and room for every fairy piece.
+const PIECE_KINDS: usize = 6 + MAX_FAIRY_PIECES;
+
+/// Random keys for Zobrist hashing, generated at compile time.
+struct Keys {
+    /// Indexed by `Square::index`.
+    pieces: [[[u64; SQUARES]; PIECE_KINDS]; 2],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant_file: [u64; MAX_FILES as usize],
+}
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; SQUARES]; PIECE_KINDS]; 2],
+        black_to_move: 0,
+        castling: [0; 4],
+        en_passant_file: [0; MAX_FILES as usize],
+    };
+    let mut state = 0x5eed;
+    let mut color = 0;
+    while color < 2 {
+        let mut ptype = 0;
+        while ptype < PIECE_KINDS {
+            let mut square = 0;
+            while square < SQUARES {
+                let (next, key) = splitmix64(state);
+                state = next;
+                keys.pieces[color][ptype][square] = key;
+                square += 1;
+            }
+            ptype += 1;
+        }
+        color += 1;
+    }
+    let (next, key) = splitmix64(state);
+    state = next;
+    keys.black_to_move = key;
+    let mut i = 0;
+    while i < 4 {
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys.castling[i] = key;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < MAX_FILES as usize {
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys.en_passant_file[i] = key;
+        i += 1;
+    }
+    keys
+}
+
+const KEYS: Keys = generate_keys();
+
+pub fn piece_index(ptype: PieceType) -> usize {
+    match ptype {
+        PieceType::King => 0,
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Rook => 3,
+        PieceType::Bishop => 4,
+        PieceType::Queen => 5,
+        PieceType::Fairy(id) => 6 + id as usize,
+    }
+}
+
+/// Zobrist hash of the position, identical for transpositions.
+pub fn hash(board: &Board) -> u64 {
+    let mut hash = 0;
+    for (square, ptype, color) in board.pieces() {
+        let color = match color {
+            PieceColor::White => 0,
+            PieceColor::Black => 1,
+        };
+        hash ^= KEYS.pieces[color][piece_index(ptype)][square.index()];
+    }
+    if board.side_to_move == PieceColor::Black {
+        hash ^= KEYS.black_to_move;
+    }
+    let castling = [
+        board.castling.white_king_side,
+        board.castling.white_queen_side,
+        board.castling.black_king_side,
+        board.castling.black_queen_side,
+    ];
+    for (i, &allowed) in castling.iter().enumerate() {
+        if allowed {
+            hash ^= KEYS.castling[i];
+        }
+    }
+    if let Some(square) = board.en_passant {
+        hash ^= KEYS.en_passant_file[square.file as usize];
+    }
+    // Checks given and pockets only matter in a few variants, so they are
+    // hashed as a whole instead of getting keys of their own
+    if board.variant_state != VariantState::default() {
+        let mut hasher = DefaultHasher::new();
+        board.variant_state.hash(&mut hasher);
+        hash ^= hasher.finish();
+    }
+    hash
+}
@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::board::BoardSquare;
+use crate::picking::Selection;
+use crate::rules::{Board, GameRecord, Move, Square};
+
+pub fn init(app: &mut App) {
+    app.add_system(update_highlights);
+}
+
+/// What a board square currently shows, in order of priority.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Selected,
+    Capture,
+    Destination,
+    LastMove,
+    None,
+}
+
+/// Materials for every `Highlight` state of a square.
+pub struct SquareMaterials {
+    pub light: Handle<StandardMaterial>,
+    pub dark: Handle<StandardMaterial>,
+    pub selected: Handle<StandardMaterial>,
+    pub capture: Handle<StandardMaterial>,
+    pub destination: Handle<StandardMaterial>,
+    pub last_move: Handle<StandardMaterial>,
+}
+
+impl SquareMaterials {
+    pub fn get(&self, square: Square, highlight: Highlight) -> &Handle<StandardMaterial> {
+        match highlight {
+            Highlight::Selected => &self.selected,
+            Highlight::Capture => &self.capture,
+            Highlight::Destination => &self.destination,
+            Highlight::LastMove => &self.last_move,
+            Highlight::None if (square.file + square.rank) % 2 == 0 => &self.dark,
+            Highlight::None => &self.light,
+        }
+    }
+}
+
+/// The highlight `square` should show for the current selection.
+pub fn highlight_for(
+    square: Square,
+    selected: Option<Square>,
+    destinations: &[Move],
+    last_move: Option<Move>,
+) -> Highlight {
+    if selected == Some(square) {
+        return Highlight::Selected;
+    }
+    if let Some(mv) = destinations.iter().find(|mv| mv.to == square) {
+        return if mv.is_capture() {
+            Highlight::Capture
+        } else {
+            Highlight::Destination
+        };
+    }
+    match last_move {
+        Some(mv) if mv.from == square || mv.to == square => Highlight::LastMove,
+        _ => Highlight::None,
+    }
+}
+
+fn update_highlights(
+    board: Res<Board>,
+    selection: Res<Selection>,
+    record: Res<GameRecord>,
+    materials: Res<SquareMaterials>,
+    mut squares: Query<(&BoardSquare, &mut Highlight, &mut Handle<StandardMaterial>)>,
+) {
+    if !board.is_changed() && !selection.is_changed() {
+        return;
+    }
+
+    let destinations = match (selection.square, selection.drop) {
+        (Some(square), _) => board.legal_moves_from(square),
+        (None, Some(ptype)) => board
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.dropped() == Some(ptype))
+            .collect(),
+        (None, None) => Vec::new(),
+    };
+    let last_move = record.moves.last().map(|recorded| recorded.mv);
+
+    for (&BoardSquare(square), mut highlight, mut material) in squares.iter_mut() {
+        let next = highlight_for(square, selection.square, &destinations, last_move);
+        if *highlight != next {
+            *highlight = next;
+            *material = materials.get(square, next).clone();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, PieceChanges};
+use crate::network::NetGame;
+use crate::outcome::DrawOffer;
+use crate::player::{PlayerKind, Players};
+use crate::rules::{
+    Board, GameRecord, GameResult, Move, PieceChange, PieceColor, RecordedMove, Undo,
+};
+use crate::save::Replay;
+
+pub fn init(app: &mut App) {
+    app.init_resource::<History>()
+        .add_event::<GoToPly>()
+        .add_system(
+            navigate_history
+                .after(BoardSystem::ApplyMoves)
+                .before(BoardSystem::SyncPieces),
+        );
+}
+
+/// Request to show the position after that many moves of the whole game.
+pub struct GoToPly(pub usize);
+
+/// The moves played on the `Board` as a stack of reversible moves, and the
+/// ones taken back since, which can be redone until another move is played.
+#[derive(Default)]
+pub struct History {
+    played: Vec<(Move, Undo)>,
+    undone: Vec<RecordedMove>,
+    /// Result and Termination tag of the finished game while browsing back.
+    ended: Option<(GameResult, Option<String>)>,
+}
+
+impl History {
+    /// Records a move just made on the `Board`. Moves taken back are dropped.
+    pub fn push(&mut self, mv: Move, undo: Undo) {
+        self.played.push((mv, undo));
+        self.undone.clear();
+        self.ended = None;
+    }
+
+    /// True while an earlier position is shown. Engines wait, the clock
+    /// keeps running for the side to move at the end of the game.
+    pub fn browsing(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// The side to move at the end of the game, `board` being the position
+    /// shown.
+    pub fn side_to_move(&self, board: &Board) -> PieceColor {
+        if self.undone.len() % 2 == 0 {
+            board.side_to_move
+        } else {
+            board.side_to_move.opponent()
+        }
+    }
+
+    /// Whether the game is over, also while browsing back from its end.
+    pub fn game_over(&self, record: &GameRecord) -> bool {
+        record.result != GameResult::Ongoing || self.ended.is_some()
+    }
+
+    /// The moves of the whole game, the ones taken back included.
+    pub fn all_moves<'a>(
+        &'a self,
+        record: &'a GameRecord,
+    ) -> impl Iterator<Item = &'a RecordedMove> + 'a {
+        record.moves.iter().chain(self.undone.iter().rev())
+    }
+
+    /// `record` with the moves taken back played again, i.e. the whole game.
+    pub fn whole_game(&self, record: &GameRecord) -> GameRecord {
+        let mut record = record.clone();
+        record.moves.extend(self.undone.iter().rev().cloned());
+        if let Some((result, termination)) = &self.ended {
+            record.result = *result;
+            if let Some(termination) = termination {
+                record.set_tag("Termination", termination);
+            }
+        }
+        record
+    }
+
+    /// Takes back the last move and returns how the pieces changed.
+    fn undo(&mut self, board: &mut Board, record: &mut GameRecord) -> Option<Vec<PieceChange>> {
+        let (mv, undo) = self.played.pop()?;
+        board.unmake_move(&undo);
+        self.undone.extend(record.moves.pop());
+        if record.result != GameResult::Ongoing {
+            let termination = record.tag("Termination").map(str::to_string);
+            self.ended = Some((record.result, termination));
+            record.result = GameResult::Ongoing;
+            record.tags.retain(|(name, _)| name != "Termination");
+        }
+        Some(board.unmake_piece_changes(mv))
+    }
+
+    /// Plays the last move taken back again and returns how the pieces
+    /// changed.
+    fn redo(&mut self, board: &mut Board, record: &mut GameRecord) -> Option<Vec<PieceChange>> {
+        let recorded = self.undone.pop()?;
+        let mv = recorded.mv;
+        let changes = board.piece_changes(mv);
+        self.played.push((mv, board.make_reversible_move(mv)));
+        record.moves.push(recorded);
+        if !self.browsing() {
+            if let Some((result, termination)) = self.ended.take() {
+                record.result = result;
+                if let Some(termination) = termination {
+                    record.set_tag("Termination", &termination);
+                }
+            }
+        }
+        Some(changes)
+    }
+}
+
+/// Left and Right step through the game, Home and End jump to its start and
+/// its last move. Ctrl+Z takes moves back until a human is to move, Ctrl+Y
+/// redoes one. Playing a move from an earlier position drops the later ones,
+/// and only then do the clocks go back to that position.
+/// Network games only go forward.
+fn navigate_history(
+    input: Res<Input<KeyCode>>,
+    players: Res<Players>,
+    replay: Option<Res<Replay>>,
+    net_game: Option<Res<NetGame>>,
+    mut history: ResMut<History>,
+    mut board: ResMut<Board>,
+    mut record: ResMut<GameRecord>,
+    mut offer: ResMut<DrawOffer>,
+    mut go_to: EventReader<GoToPly>,
+    mut changes: EventWriter<PieceChanges>,
+) {
+    // A replay plays its own moves, and the other end of a network game
+    // keeps playing from the position it has
+    if replay.is_some() || net_game.is_some() {
+        return;
+    }
+    let ply = record.moves.len();
+    let last = ply + history.undone.len();
+    let start_side = record.start.side_to_move;
+    let human_at = |ply: usize| {
+        let side = if ply % 2 == 0 {
+            start_side
+        } else {
+            start_side.opponent()
+        };
+        players.get(side) == PlayerKind::Human
+    };
+
+    let ctrl = input.pressed(KeyCode::LControl) || input.pressed(KeyCode::RControl);
+    let mut target = go_to.iter().last().map(|&GoToPly(target)| target.min(last));
+    if input.just_pressed(KeyCode::Left) {
+        target = Some(ply.saturating_sub(1));
+    } else if input.just_pressed(KeyCode::Right) || (ctrl && input.just_pressed(KeyCode::Y)) {
+        target = Some((ply + 1).min(last));
+    } else if input.just_pressed(KeyCode::Home) {
+        target = Some(0);
+    } else if input.just_pressed(KeyCode::End) {
+        target = Some(last);
+    } else if ctrl && input.just_pressed(KeyCode::Z) {
+        target = (0..ply)
+            .rev()
+            .find(|&ply| human_at(ply))
+            .or_else(|| ply.checked_sub(1));
+    }
+    let target = match target {
+        Some(target) if target != ply => target,
+        _ => return,
+    };
+
+    while record.moves.len() > target {
+        match history.undo(&mut board, &mut record) {
+            Some(undone) => changes.send(PieceChanges(undone)),
+            None => break,
+        }
+    }
+    while record.moves.len() < target {
+        match history.redo(&mut board, &mut record) {
+            Some(redone) => changes.send(PieceChanges(redone)),
+            None => break,
+        }
+    }
+
+    offer.0 = None;
+}
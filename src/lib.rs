@@ -0,0 +1,6 @@
+//! Headless chess logic shared by the game and the `uci` engine binary.
+
+pub mod engine;
+pub mod net;
+pub mod rules;
+pub mod uci;
@@ -3,6 +3,7 @@
 
 use bevy::{
     app::AppExit,
+    asset::AssetServerSettings,
     diagnostic::{EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     input::mouse::{MouseButtonInput, MouseMotion, MouseWheel},
     input::ElementState,
@@ -12,10 +13,15 @@ use bevy::{
     window::WindowMode::*,
 };
 
-// use bevy_egui::{egui, EguiContext, EguiPlugin};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bevy_editor_pls::*;
 
+use bevy_chess::{engine, net, rules, uci};
+use net::{NetError, NetHost, Role};
+use rules::{Board, BoardSize, ChessClock, GameRecord, TimeControl, VariantKind};
+
 fn spherical_to_cartesian(spherical: &Vec3) -> Vec3 {
     let (r, theta, phi) = (spherical.x, spherical.y, spherical.z);
     let x = r * phi.cos() * theta.sin();
@@ -24,24 +30,58 @@ fn spherical_to_cartesian(spherical: &Vec3) -> Vec3 {
     Vec3::new(x, y, z)
 }
 
+mod ai;
+use ai::AiSettings;
+
+mod animation;
+
+mod board;
+use board::BoardSquare;
+
+mod clock;
+
 mod entity;
 use entity::*;
 
+mod highlight;
+use highlight::{Highlight, SquareMaterials};
+
+mod history;
+
+mod network;
+use network::{NetGame, Spectators};
+
+mod outcome;
+
+mod panel;
+
+mod picking;
+
 mod piece;
 use piece::*;
 
-mod window;
+mod piece_set;
+
+mod player;
+use player::{parse_sides, PlayerKind, Players};
+
+mod promotion;
+
+mod save;
 
-#[rustfmt::skip]
-const RESET_FOCUS: [f32; 3] = [
-    14 as f32 / 2.0,
-    0.0,
-    21 as f32 / 2.0,
-];
+mod theme;
+
+mod uci_engine;
+use uci_engine::UciEngine;
+
+mod window;
 
 const SPEED: f32 = 5.0;
 const SENS: f32 = 500.0;
 
+/// Fairy piece definitions read when no `--pieces` file is given.
+const PIECES_FILE: &str = "assets/pieces/fairy.toml";
+
 #[derive(Default)]
 struct MyGame {
     button: bool,
@@ -53,15 +93,349 @@ struct MyGame {
 #[derive(Component)]
 struct Player(Transform);
 
-// fn ui_example(mut egui_context: ResMut<EguiContext>) {
-//     egui::Window::new("debug").show(egui_context.ctx_mut(), |ui| {
-//         ui.label("fps: 123");
-//     });
-// }
+/// Value following `name` on the command line, e.g. `--fen "<fen>"`.
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+fn sides_arg(name: &str, sides: &str) -> &'static [PieceColor] {
+    parse_sides(sides).unwrap_or_else(|| {
+        eprintln!("{} expects white, black or both, got '{}'", name, sides);
+        std::process::exit(1);
+    })
+}
+
+/// Defines the fairy pieces of `--pieces <file>`, or of `PIECES_FILE` if it
+/// exists, and returns how they look.
+fn load_pieces() -> FairyLooks {
+    let given = arg_value("--pieces");
+    let path = given.clone().unwrap_or_else(|| PIECES_FILE.to_string());
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        // Only the standard pieces then
+        Err(_) if given.is_none() => return FairyLooks::default(),
+        Err(err) => {
+            eprintln!("failed to read '{}': {}", path, err);
+            std::process::exit(1);
+        }
+    };
+    rules::define_fairy_pieces(&text)
+        .map_err(|err| err.to_string())
+        .and_then(|_| FairyLooks::parse(&text))
+        .unwrap_or_else(|err| {
+            eprintln!("invalid pieces file '{}': {}", path, err);
+            std::process::exit(1);
+        })
+}
+
+/// Waits for a player to connect to `--host <address>` and sends them the
+/// game of `record`. The host plays `--host-side`, white unless given.
+/// Spectators may join before and after.
+fn host_game(address: &str, record: &GameRecord) -> (NetGame, Spectators) {
+    let side = arg_value("--host-side").unwrap_or_else(|| "white".to_string());
+    let color = match side.as_str() {
+        "white" => PieceColor::White,
+        "black" => PieceColor::Black,
+        _ => {
+            eprintln!("--host-side expects white or black, got '{}'", side);
+            std::process::exit(1);
+        }
+    };
+    let host = NetHost::bind(&net::with_default_port(address)).unwrap_or_else(|err| {
+        eprintln!("failed to host on '{}': {}", address, err);
+        std::process::exit(1);
+    });
+    let listening = host.local_addr();
+    println!("waiting for an opponent on {}", listening);
+    let mut spectators = Spectators::new(host);
+    let connection = loop {
+        match spectators.accept() {
+            Ok((connection, Role::Player)) => break connection,
+            // Watching an empty board until the opponent is there
+            Ok((connection, Role::Spectator)) => spectators.admit(connection, record, None, None),
+            Err(NetError::Closed) => {
+                eprintln!("stopped listening on {}", listening);
+                std::process::exit(1);
+            }
+            Err(err) => eprintln!("a player failed to connect: {}", err),
+        }
+    };
+    if let Ok(peer) = connection.peer_addr() {
+        println!("{} joined", peer);
+    }
+    let game = NetGame::host(connection, color, record).unwrap_or_else(|err| {
+        eprintln!("failed to start the game: {}", err);
+        std::process::exit(1);
+    });
+    (game, spectators)
+}
+
+/// Starting position played as `--variant <name>`, standard chess unless
+/// given. A `--fen` position is taken as it is, otherwise the variant sets
+/// up its pieces.
+fn starting_board() -> Board {
+    let mut board = starting_position();
+    let name = match arg_value("--variant") {
+        Some(name) => name,
+        None => return board,
+    };
+    let kind = VariantKind::parse(&name).unwrap_or_else(|| {
+        let names: Vec<&str> = VariantKind::ALL.iter().map(|kind| kind.name()).collect();
+        eprintln!(
+            "unknown --variant '{}', expected one of: {}",
+            name,
+            names.join(", ")
+        );
+        std::process::exit(1);
+    });
+    let size = board.size();
+    if !kind.rules().supports(size) {
+        eprintln!("{} can't be played on a {} board", kind.name(), size);
+        std::process::exit(1);
+    }
+    if arg_value("--fen").is_some() {
+        board.variant = kind;
+        board
+    } else {
+        kind.setup(board).expect("size checked above")
+    }
+}
+
+/// Starting position, taken from `--fen "<fen>"` when given, or Chess960
+/// position `--chess960 <0-959|random>`. `--seed <n>` repeats a random one.
+/// Otherwise the standard setup for `--board <files>x<ranks>`, 8x8 unless
+/// given. A FEN and Chess960 bring their own board, so they don't go with
+/// `--board`.
+fn starting_position() -> Board {
+    if arg_value("--board").is_some()
+        && (arg_value("--fen").is_some() || arg_value("--chess960").is_some())
+    {
+        eprintln!("--board can't be used with --fen or --chess960");
+        std::process::exit(1);
+    }
+    if let Some(fen) = arg_value("--fen") {
+        return Board::from_fen(&fen).unwrap_or_else(|err| {
+            eprintln!("invalid --fen '{}': {}", fen, err);
+            std::process::exit(1);
+        });
+    }
+    let position = match arg_value("--chess960") {
+        Some(position) => position,
+        None => return sized_start(),
+    };
+    let index = if position == "random" {
+        let seed = match arg_value("--seed") {
+            Some(seed) => seed.parse().unwrap_or_else(|_| {
+                eprintln!("--seed expects a number, got '{}'", seed);
+                std::process::exit(1);
+            }),
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64),
+        };
+        rules::chess960_index_from_seed(seed)
+    } else {
+        position.parse().unwrap_or(rules::CHESS960_POSITIONS)
+    };
+    match Board::chess960(index) {
+        Some(board) => {
+            println!("Chess960 position {}", index);
+            board
+        }
+        None => {
+            eprintln!("--chess960 expects 0-959 or random, got '{}'", position);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The standard setup for the `--board` size.
+fn sized_start() -> Board {
+    let size = match arg_value("--board") {
+        Some(size) => size,
+        None => return Board::start(),
+    };
+    BoardSize::parse(&size)
+        .and_then(Board::start_with_size)
+        .unwrap_or_else(|| {
+            eprintln!("--board expects 5x5, 6x6, 8x8 or 10x8, got '{}'", size);
+            std::process::exit(1);
+        })
+}
+
+/// `perft <depth> [fen]` and `divide <depth> [fen]` count move generator
+/// leaf nodes without starting the game.
+fn run_perft(args: &[String]) {
+    let depth = match args.get(2).and_then(|depth| depth.parse().ok()) {
+        Some(depth) => depth,
+        None => {
+            eprintln!("usage: {} <depth> [fen]", args[1]);
+            std::process::exit(1);
+        }
+    };
+    let board = match args.get(3..).filter(|fen| !fen.is_empty()) {
+        Some(fen) => Board::from_fen(&fen.join(" ")).unwrap_or_else(|err| {
+            eprintln!("invalid fen: {}", err);
+            std::process::exit(1);
+        }),
+        None => Board::start(),
+    };
+
+    let start = std::time::Instant::now();
+    let nodes = if args[1] == "divide" {
+        let mut divided = rules::divide(&board, depth);
+        divided.sort_by_key(|(mv, _)| mv.to_string());
+        for (mv, nodes) in &divided {
+            println!("{}: {}", mv, nodes);
+        }
+        println!();
+        divided.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        rules::perft(&board, depth)
+    };
+    let elapsed = start.elapsed();
+    println!("Nodes searched: {}", nodes);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
 
 fn main() {
+    // Before any position is read, FENs may contain fairy pieces
+    let fairy_looks = load_pieces();
+
+    let args: Vec<String> = std::env::args().collect();
+    if let Some("perft" | "divide") = args.get(1).map(String::as_str) {
+        run_perft(&args);
+        return;
+    }
+
     let mut app = App::new();
 
+    // `--host <address>` plays against another instance started with
+    // `--connect <address>`, e.g. `--host 0.0.0.0` and `--connect 192.168.1.20`
+    // on port 7878 unless given. Others can `--watch <address>`, following the
+    // host's camera until C is pressed. The host sets up the game, a `--pgn`
+    // one can't be played over the network.
+    let hosting = arg_value("--host");
+    let joining = match (arg_value("--connect"), arg_value("--watch")) {
+        (Some(address), _) => Some((address, Role::Player)),
+        (None, Some(address)) => Some((address, Role::Spectator)),
+        (None, None) => None,
+    };
+    if (hosting.is_some() || joining.is_some()) && arg_value("--pgn").is_some() {
+        eprintln!("--pgn can't be used with --host, --connect or --watch");
+        std::process::exit(1);
+    }
+    let joined = joining.map(|(address, role)| {
+        NetGame::join(&address, role).unwrap_or_else(|err| {
+            eprintln!("failed to join '{}': {}", address, err);
+            std::process::exit(1);
+        })
+    });
+    if let Some((game, _)) = &joined {
+        match game.color {
+            Some(color) => println!("joined the game, playing {:?}", color),
+            None => println!("watching the game"),
+        }
+    }
+
+    // `--pieces <file>` defines fairy pieces, see `assets/pieces/fairy.toml`.
+    // `--pgn <file>` replays a saved game from its starting position.
+    // `--clock <control>` plays with clocks, e.g. `300+2` or `40/7200:3600`,
+    // loaded games keep theirs unless overridden, joined games play the host's.
+    let time_control = arg_value("--clock")
+        .filter(|_| joined.is_none())
+        .map(|control| {
+            control.parse::<TimeControl>().unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            })
+        });
+    let mut record = match arg_value("--pgn") {
+        Some(path) => {
+            let (record, replay) = save::load(path.as_ref()).unwrap_or_else(|err| {
+                eprintln!("failed to load '{}': {}", path, err);
+                std::process::exit(1);
+            });
+            app.insert_resource(replay);
+            record
+        }
+        None => match &joined {
+            Some((_, setup)) => setup.record(),
+            None => GameRecord::new(starting_board()),
+        },
+    };
+    let time_control = time_control.or_else(|| {
+        record
+            .tag("TimeControl")
+            .and_then(|control| control.parse().ok())
+    });
+    if let Some(control) = time_control {
+        record.set_tag("TimeControl", &control.to_string());
+        // A joined game may be under way already
+        let mut clock = ChessClock::new(control);
+        clock.restore(&record);
+        app.insert_resource(clock);
+    }
+    let net_game = match hosting {
+        Some(address) => {
+            let (game, spectators) = host_game(&address, &record);
+            app.insert_resource(spectators);
+            Some(game)
+        }
+        None => joined.map(|(game, _)| game),
+    };
+    app.insert_resource(record.board_at(record.moves.len()))
+        .insert_resource(record)
+        .insert_resource(fairy_looks);
+
+    // `--ai <white|black|both>` hands sides to the computer, `--ai-level <1-10>`
+    // sets its strength. `--uci <path>` lets an external engine play the sides
+    // given by `--uci-side` (black by default), `--uci-movetime <ms>` per move.
+    let mut players = Players::default();
+    if let Some(sides) = arg_value("--ai") {
+        for &color in sides_arg("--ai", &sides) {
+            players.set(color, PlayerKind::Computer);
+        }
+    }
+    if let Some(path) = arg_value("--uci") {
+        let sides = arg_value("--uci-side").unwrap_or_else(|| "black".to_string());
+        for &color in sides_arg("--uci-side", &sides) {
+            players.set(color, PlayerKind::External);
+        }
+        let movetime = arg_value("--uci-movetime").map_or(1000, |ms| {
+            ms.parse().unwrap_or_else(|_| {
+                eprintln!("--uci-movetime expects milliseconds, got '{}'", ms);
+                std::process::exit(1);
+            })
+        });
+        let engine =
+            UciEngine::spawn(&path, Duration::from_millis(movetime)).unwrap_or_else(|err| {
+                eprintln!("failed to start engine '{}': {}", path, err);
+                std::process::exit(1);
+            });
+        app.insert_resource(engine);
+    }
+    if let Some(game) = net_game {
+        for color in [PieceColor::White, PieceColor::Black] {
+            if Some(color) != game.color {
+                players.set(color, PlayerKind::Remote);
+            }
+        }
+        app.insert_resource(game);
+    }
+    app.insert_resource(players);
+    if let Some(level) = arg_value("--ai-level") {
+        let level = level.parse().unwrap_or_else(|_| {
+            eprintln!("--ai-level expects a number, got '{}'", level);
+            std::process::exit(1);
+        });
+        app.insert_resource(AiSettings::from_level(level));
+    }
+
     app.insert_resource(Msaa { samples: 4 })
         .insert_resource(WindowDescriptor {
             title: "Chess!".to_string(),
@@ -72,6 +446,11 @@ fn main() {
             mode: BorderlessFullscreen,
             ..Default::default()
         })
+        // Picks up changes to asset files while running, e.g. the piece sets
+        .insert_resource(AssetServerSettings {
+            watch_for_changes: true,
+            ..Default::default()
+        })
         .init_resource::<MyGame>()
         .add_plugins(DefaultPlugins)
         .add_startup_system(setup)
@@ -80,17 +459,31 @@ fn main() {
         .add_system(exit)
         .add_system(print_mouse_events_system)
         .add_system(wasd)
-        // .add_plugin(EguiPlugin)
-        // Systems that create Egui widgets should be run during the `CoreStage::Update` stage,
-        // or after the `EguiSystem::BeginFrame` system (which belongs to the `CoreStage::PreUpdate` stage).
+        // Also adds the EguiPlugin. Systems that create Egui widgets, like the
+        // `panel` and `promotion` ones, run during the `CoreStage::Update` stage.
         .add_plugin(EditorPlugin)
-        // .add_system(ui_example)
         .add_system(camera_writer)
         .add_plugin(EntityCountDiagnosticsPlugin::default())
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
         .add_plugin(LogDiagnosticsPlugin::default());
 
     window::init(&mut app);
+    board::init(&mut app);
+    piece_set::init(&mut app);
+    clock::init(&mut app);
+    outcome::init(&mut app);
+    save::init(&mut app);
+    history::init(&mut app);
+    panel::init(&mut app);
+    picking::init(&mut app);
+    promotion::init(&mut app);
+    highlight::init(&mut app);
+    theme::init(&mut app);
+    animation::init(&mut app);
+    player::init(&mut app);
+    ai::init(&mut app);
+    uci_engine::init(&mut app);
+    network::init(&mut app);
     Block::init(&mut app);
 
     app.run();
@@ -99,6 +492,7 @@ fn main() {
 fn setup(
     mut commands: Commands,
     mut game: ResMut<MyGame>,
+    board: Res<Board>,
     asset_server: Res<AssetServer>,
     // mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -111,8 +505,11 @@ fn setup(
     let size = 32.0;
     let scale = 1.0 / size;
 
+    // Far enough away to see the whole board
+    let focus = board_center(board.size());
+    let extent = board.size().files.max(board.size().ranks) as f32;
     game.button = false;
-    game.camera = Vec3::new(6.00, 0.94, 3.51);
+    game.camera = Vec3::new(0.75 * extent, 0.94, 3.51);
     game.pos = Vec3::new(0.0, 0.0, 0.0);
     game.orig_camera = None;
 
@@ -155,7 +552,7 @@ fn setup(
     //                     Quat::from_xyzw(-0.3, -0.5, -0.3, 0.5).normalize(),
     //                     Vec3::new(-7.0, 20.0, 4.0),
     //                 ))
-    //                 .looking_at(Vec3::from(RESET_FOCUS), Vec3::Y),
+    //                 .looking_at(focus, Vec3::Y),
     //                 ..Default::default()
     //             });
     //     });
@@ -167,14 +564,14 @@ fn setup(
                 Quat::from_xyzw(-0.3, -0.5, -0.3, 0.5).normalize(),
                 Vec3::new(-7.0, 20.0, 4.0),
             ))
-            .looking_at(Vec3::from(RESET_FOCUS), Vec3::Y),
+            .looking_at(focus, Vec3::Y),
             ..Default::default()
         });
 
     commands
         // Light
         .spawn_bundle(PointLightBundle {
-            transform: Transform::from_translation(Vec3::new(4.0, 8.0, 4.0)),
+            transform: Transform::from_translation(focus + Vec3::new(0.5, 8.0, 0.5)),
             ..Default::default()
         });
 }
@@ -188,82 +585,69 @@ fn exit(input: Res<Input<KeyCode>>, mut app_exit_events: EventWriter<AppExit>) {
 
 fn create_board(
     mut commands: Commands,
+    board: Res<Board>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     // Add meshes and materials
     let mesh = meshes.add(Mesh::from(shape::Plane { size: 1. }));
-    let white_material = materials.add(Color::rgb(1., 0.9, 0.9).into());
-    let black_material = materials.add(Color::rgb(0., 0.1, 0.1).into());
+    let square_materials = SquareMaterials {
+        light: materials.add(Color::rgb(1., 0.9, 0.9).into()),
+        dark: materials.add(Color::rgb(0., 0.1, 0.1).into()),
+        selected: materials.add(Color::rgb(0.9, 0.8, 0.2).into()),
+        capture: materials.add(Color::rgb(0.8, 0.2, 0.2).into()),
+        destination: materials.add(Color::rgb(0.3, 0.7, 0.3).into()),
+        last_move: materials.add(Color::rgb(0.4, 0.5, 0.8).into()),
+    };
 
-    // Spawn 64 squares
-    for i in 0..8 {
-        for j in 0..8 {
-            commands.spawn_bundle(PbrBundle {
+    // Spawn one entity per square of the board
+    for square in board.size().squares() {
+        commands
+            .spawn_bundle(PbrBundle {
                 mesh: mesh.clone(),
-                // Change material according to position to get alternating pattern
-                material: if (i + j + 1) % 2 == 0 {
-                    white_material.clone()
-                } else {
-                    black_material.clone()
-                },
-                transform: Transform::from_translation(Vec3::new(i as f32, 0., j as f32)),
+                // Material alternates according to position
+                material: square_materials.get(square, Highlight::None).clone(),
+                transform: Transform::from_translation(square_translation(square)),
                 ..Default::default()
-            });
-        }
+            })
+            .insert(BoardSquare(square))
+            .insert(Highlight::None);
     }
+
+    commands.insert_resource(square_materials);
 }
 
 fn create_pieces(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    fairy_looks: Res<FairyLooks>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // Load all the meshes
-    let king_mesh: Handle<Mesh> = asset_server.load("model/chess_kit/pieces.glb#Mesh0/Primitive0");
-    let king_cross_mesh: Handle<Mesh> =
-        asset_server.load("model/chess_kit/pieces.glb#Mesh1/Primitive0");
-    let pawn_mesh: Handle<Mesh> = asset_server.load("model/chess_kit/pieces.glb#Mesh2/Primitive0");
-    let knight_1_mesh: Handle<Mesh> =
-        asset_server.load("model/chess_kit/pieces.glb#Mesh3/Primitive0");
-    let knight_2_mesh: Handle<Mesh> =
-        asset_server.load("model/chess_kit/pieces.glb#Mesh4/Primitive0");
-    let rook_mesh: Handle<Mesh> = asset_server.load("model/chess_kit/pieces.glb#Mesh5/Primitive0");
-    let bishop_mesh: Handle<Mesh> =
-        asset_server.load("model/chess_kit/pieces.glb#Mesh6/Primitive0");
-    let queen_mesh: Handle<Mesh> = asset_server.load("model/chess_kit/pieces.glb#Mesh7/Primitive0");
-
-    // Add some materials
-    let white_material = materials.add(Color::rgb(1., 0.8, 0.8).into());
-    let black_material = materials.add(Color::rgb(0., 0.2, 0.2).into());
-
-    for piece in BASE_PIECES {
-        let material = match piece.color {
-            PieceColor::Black => &white_material,
-            PieceColor::White => &black_material,
-        };
-        let meshes = match piece.ptype {
-            PieceType::King => {
-                vec![&king_mesh, &king_cross_mesh]
-            }
-            PieceType::Pawn => {
-                vec![&pawn_mesh]
-            }
-            PieceType::Knight => {
-                vec![&knight_1_mesh, &knight_2_mesh]
-            }
-            PieceType::Rook => {
-                vec![&rook_mesh]
+    let white = Color::rgb(1., 0.8, 0.8);
+    let black = Color::rgb(0., 0.2, 0.2);
+
+    // Tinted fairy pieces get the side's colour mixed with their own,
+    // assigned to the sides like in `PieceAssets::material`
+    let mut fairy_materials = HashMap::new();
+    for (&ptype, look) in &fairy_looks.0 {
+        if let Some(tint) = look.tint {
+            for (color, base) in [(PieceColor::Black, white), (PieceColor::White, black)] {
+                fairy_materials.insert((ptype, color), materials.add(tinted(base, tint).into()));
             }
-            PieceType::Bishop => {
-                vec![&bishop_mesh]
-            }
-            PieceType::Queen => {
-                vec![&queen_mesh]
-            }
-        };
-        Piece::spawn(&mut commands, piece, meshes.as_slice(), material);
+        }
     }
+
+    // Add some materials, `theme` recolours them and picks the piece set
+    // whose meshes `piece_set` takes once it is loaded. The piece entities
+    // themselves are spawned by `board::sync_pieces` from the `Board`.
+    commands.insert_resource(PieceAssets {
+        set: Handle::default(),
+        models: HashMap::new(),
+        white_material: materials.add(white.into()),
+        black_material: materials.add(black.into()),
+        fairy_looks: fairy_looks.0.clone(),
+        fairy_materials,
+    });
 }
 
 /// This system prints out all mouse events as they come in
@@ -336,13 +720,18 @@ fn wasd(
     game.pos += next_pos;
 }
 
-fn camera_writer(game: Res<MyGame>, mut camera_transform: Query<(&mut Transform, &Camera)>) {
+fn camera_writer(
+    game: Res<MyGame>,
+    board: Res<Board>,
+    mut camera_transform: Query<(&mut Transform, &Camera)>,
+) {
     let new_coords = spherical_to_cartesian(&game.camera);
+    let focus = board_center(board.size());
 
-    // look at that new camera's actual focus
+    // look at that new camera's actual focus, orbiting the middle of the board
     for (mut transform, camera) in camera_transform.iter_mut() {
         if camera.name == Some(CameraPlugin::CAMERA_3D.to_string()) {
-            *transform = Transform::from_translation(new_coords).looking_at(Vec3::ZERO, Vec3::Y);
+            *transform = Transform::from_translation(focus + new_coords).looking_at(focus, Vec3::Y);
             transform.translation.x += game.pos.x;
             transform.translation.y += game.pos.y;
             transform.translation.z += game.pos.z;
@@ -0,0 +1,522 @@
+//! The protocol for playing between two instances of the game over TCP, and
+//! the connections carrying it.
+//!
+//! Messages are lines of text. Both ends open with `chess <version>` and
+//! hang up on a version they don't speak. The connecting end says whether
+//! it wants to play or watch, then the host sends the `game`, with the moves
+//! so far. Moves are written as in UCI and carry the time the mover has
+//! left, so the clocks on all ends agree after every move.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{
+    mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use crate::rules::{Board, GameRecord, Move, PieceColor, TimeControl, VariantKind};
+use crate::uci;
+
+/// Sent in the opening `chess` line. Peers with another version are turned
+/// away.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Port used when an address is given without one.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// How long to wait for the other end during the handshake.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `address` with `DEFAULT_PORT` added if it has no port, e.g. `192.168.1.20`.
+pub fn with_default_port(address: &str) -> String {
+    let has_port =
+        matches!(address.rsplit_once(':'), Some((_, port)) if port.parse::<u16>().is_ok());
+    if address.parse::<Ipv6Addr>().is_ok() {
+        format!("[{}]:{}", address, DEFAULT_PORT)
+    } else if has_port {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
+
+/// What the connecting end of a game wants to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Player,
+    /// Follows the game without taking part in it.
+    Spectator,
+}
+
+/// The game the host sends once a player or spectator has connected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSetup {
+    /// The side played by the receiver, `None` for a spectator.
+    pub color: Option<PieceColor>,
+    pub start: Board,
+    /// Moves already played from `start`.
+    pub moves: Vec<Move>,
+    pub control: Option<TimeControl>,
+}
+
+impl GameSetup {
+    /// The game as a record to continue from.
+    pub fn record(&self) -> GameRecord {
+        let mut record = GameRecord::new(self.start.clone());
+        let mut board = self.start.clone();
+        for &mv in &self.moves {
+            record.push(&board, mv);
+            board.make_move(mv);
+        }
+        if let Some(control) = &self.control {
+            record.set_tag("TimeControl", &control.to_string());
+        }
+        record
+    }
+}
+
+/// A line sent from one end of a game to the other.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetMessage {
+    /// `chess <version>`, the first line from both ends.
+    Hello { version: u32 },
+    /// `join play` or `join watch`, from the connecting end after `chess`.
+    Join(Role),
+    /// `game <white|black|watch> [variant <name>] [chess960] [clock <control>]
+    /// fen <fen> [moves <move>...]`, from the host.
+    Game(Box<GameSetup>),
+    /// `move <move> [clock <ms>]`, with the time the mover has left after it.
+    Move { mv: String, clock: Option<Duration> },
+    /// `clock <white ms> <black ms>`, the time left on both clocks.
+    Clock { white: Duration, black: Duration },
+    /// `resign <color>`, the side resigning. The side is given with every
+    /// action so the host can pass them on to spectators.
+    Resign(PieceColor),
+    /// `draw offer <color>`, which also claims a draw the rules allow.
+    OfferDraw(PieceColor),
+    /// `draw accept <color>`
+    AcceptDraw(PieceColor),
+    /// `draw decline <color>`
+    DeclineDraw(PieceColor),
+    /// `camera <distance> <polar> <azimuth>`, the host's orbit around the
+    /// board, for spectators who follow it.
+    Camera([f32; 3]),
+    /// `error <text>`, why the sender is about to hang up.
+    Error(String),
+    /// The sender is leaving the game.
+    Quit,
+    /// Anything else, which this version ignores.
+    Other(String),
+}
+
+fn color_name(color: PieceColor) -> &'static str {
+    match color {
+        PieceColor::White => "white",
+        PieceColor::Black => "black",
+    }
+}
+
+fn parse_color(token: &str) -> Option<PieceColor> {
+    match token {
+        "white" => Some(PieceColor::White),
+        "black" => Some(PieceColor::Black),
+        _ => None,
+    }
+}
+
+fn millis(time: Duration) -> u64 {
+    time.as_millis() as u64
+}
+
+impl fmt::Display for NetMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetMessage::Hello { version } => write!(f, "chess {}", version),
+            NetMessage::Join(Role::Player) => write!(f, "join play"),
+            NetMessage::Join(Role::Spectator) => write!(f, "join watch"),
+            NetMessage::Game(setup) => {
+                write!(f, "game {}", setup.color.map_or("watch", color_name))?;
+                if setup.start.variant != VariantKind::Standard {
+                    write!(
+                        f,
+                        " variant {}",
+                        setup.start.variant.name().replace(' ', "")
+                    )?;
+                }
+                if setup.start.chess960 {
+                    write!(f, " chess960")?;
+                }
+                if let Some(control) = &setup.control {
+                    write!(f, " clock {}", control)?;
+                }
+                write!(f, " fen {}", setup.start.to_fen())?;
+                if !setup.moves.is_empty() {
+                    write!(f, " moves")?;
+                    let mut board = setup.start.clone();
+                    for &mv in &setup.moves {
+                        write!(f, " {}", uci::move_text(&board, mv))?;
+                        board.make_move(mv);
+                    }
+                }
+                Ok(())
+            }
+            NetMessage::Move { mv, clock } => {
+                write!(f, "move {}", mv)?;
+                if let Some(clock) = clock {
+                    write!(f, " clock {}", millis(*clock))?;
+                }
+                Ok(())
+            }
+            NetMessage::Clock { white, black } => {
+                write!(f, "clock {} {}", millis(*white), millis(*black))
+            }
+            NetMessage::Resign(color) => write!(f, "resign {}", color_name(*color)),
+            NetMessage::OfferDraw(color) => write!(f, "draw offer {}", color_name(*color)),
+            NetMessage::AcceptDraw(color) => write!(f, "draw accept {}", color_name(*color)),
+            NetMessage::DeclineDraw(color) => write!(f, "draw decline {}", color_name(*color)),
+            NetMessage::Camera([distance, polar, azimuth]) => {
+                write!(f, "camera {} {} {}", distance, polar, azimuth)
+            }
+            NetMessage::Error(text) => write!(f, "error {}", text),
+            NetMessage::Quit => write!(f, "quit"),
+            NetMessage::Other(line) => write!(f, "{}", line),
+        }
+    }
+}
+
+impl NetMessage {
+    pub fn parse(line: &str) -> Result<NetMessage, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let millis = |token: &str| {
+            token
+                .parse()
+                .map(Duration::from_millis)
+                .map_err(|_| format!("invalid time in '{}'", line))
+        };
+        let color =
+            |token: &str| parse_color(token).ok_or_else(|| format!("invalid side in '{}'", line));
+        let message = match tokens.as_slice() {
+            ["chess", version] => NetMessage::Hello {
+                version: version
+                    .parse()
+                    .map_err(|_| format!("invalid protocol version '{}'", version))?,
+            },
+            ["game", rest @ ..] => NetMessage::Game(Box::new(parse_game(rest)?)),
+            ["move", mv] => NetMessage::Move {
+                mv: mv.to_string(),
+                clock: None,
+            },
+            ["move", mv, "clock", ms] => NetMessage::Move {
+                mv: mv.to_string(),
+                clock: Some(millis(ms)?),
+            },
+            ["clock", white, black] => NetMessage::Clock {
+                white: millis(white)?,
+                black: millis(black)?,
+            },
+            ["join", "play"] => NetMessage::Join(Role::Player),
+            ["join", "watch"] => NetMessage::Join(Role::Spectator),
+            ["resign", side] => NetMessage::Resign(color(side)?),
+            ["draw", "offer", side] => NetMessage::OfferDraw(color(side)?),
+            ["draw", "accept", side] => NetMessage::AcceptDraw(color(side)?),
+            ["draw", "decline", side] => NetMessage::DeclineDraw(color(side)?),
+            ["camera", coords @ ..] if coords.len() == 3 => {
+                let mut camera = [0.0; 3];
+                for (value, token) in camera.iter_mut().zip(coords) {
+                    *value = token
+                        .parse()
+                        .map_err(|_| format!("invalid camera in '{}'", line))?;
+                }
+                NetMessage::Camera(camera)
+            }
+            ["error", ..] => NetMessage::Error(tokens[1..].join(" ")),
+            ["quit"] => NetMessage::Quit,
+            _ => NetMessage::Other(line.to_string()),
+        };
+        Ok(message)
+    }
+}
+
+fn parse_game(tokens: &[&str]) -> Result<GameSetup, String> {
+    let color = match tokens.first() {
+        Some(&"watch") => None,
+        Some(token) => Some(parse_color(token).ok_or("game expects white, black or watch first")?),
+        None => return Err("game expects white, black or watch first".to_string()),
+    };
+    let mut variant = None;
+    let mut chess960 = false;
+    let mut control = None;
+    let mut rest = &tokens[1..];
+    loop {
+        match rest {
+            ["variant", name, more @ ..] => {
+                variant = Some(
+                    VariantKind::parse(name)
+                        .ok_or_else(|| format!("unknown variant '{}'", name))?,
+                );
+                rest = more;
+            }
+            ["chess960", more @ ..] => {
+                chess960 = true;
+                rest = more;
+            }
+            ["clock", text, more @ ..] => {
+                control = Some(text.parse::<TimeControl>().map_err(|err| err.to_string())?);
+                rest = more;
+            }
+            ["fen", more @ ..] => {
+                rest = more;
+                break;
+            }
+            _ => return Err("game expects a fen".to_string()),
+        }
+    }
+
+    let fen_end = rest
+        .iter()
+        .position(|&t| t == "moves")
+        .unwrap_or(rest.len());
+    let fen = rest[..fen_end].join(" ");
+    let mut start = Board::from_fen(&fen).map_err(|err| format!("invalid fen: {}", err))?;
+    if let Some(kind) = variant {
+        start.variant = kind;
+    }
+    start.chess960 |= chess960;
+
+    let mut board = start.clone();
+    let mut moves = Vec::new();
+    for text in rest.iter().skip(fen_end + 1) {
+        let mv = uci::parse_move(&board, text).ok_or_else(|| format!("illegal move {}", text))?;
+        board.make_move(mv);
+        moves.push(mv);
+    }
+    Ok(GameSetup {
+        color,
+        start,
+        moves,
+        control,
+    })
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The other end speaks another version of the protocol.
+    Version(u32),
+    /// A line that is not a valid message, or a message out of place.
+    Protocol(String),
+    /// The other end hung up with this `error`.
+    Refused(String),
+    Timeout,
+    Closed,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetError::Io(err) => write!(f, "{}", err),
+            NetError::Version(version) => write!(
+                f,
+                "protocol version {} is not supported, expected {}",
+                version, PROTOCOL_VERSION
+            ),
+            NetError::Protocol(err) => write!(f, "protocol error: {}", err),
+            NetError::Refused(text) => write!(f, "refused: {}", text),
+            NetError::Timeout => write!(f, "timed out"),
+            NetError::Closed => write!(f, "connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(err: io::Error) -> Self {
+        NetError::Io(err)
+    }
+}
+
+/// One end of a game connection.
+///
+/// Lines are read on a separate thread so a game loop never blocks on the
+/// network; `try_recv` picks them up.
+pub struct Connection {
+    stream: TcpStream,
+    lines: Mutex<Receiver<String>>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let reader = stream.try_clone()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(Connection {
+            stream,
+            lines: Mutex::new(receiver),
+        })
+    }
+
+    /// Connects to the host at `address`, e.g. `192.168.1.20:7878`, to play
+    /// or watch and waits for the game it sets up.
+    pub fn connect(address: &str, role: Role) -> Result<(Connection, GameSetup), NetError> {
+        let connection = Connection::new(TcpStream::connect(address)?)?;
+        connection.handshake()?;
+        connection.send(&NetMessage::Join(role))?;
+        match connection.recv(HANDSHAKE_TIMEOUT)? {
+            NetMessage::Game(setup) => Ok((connection, *setup)),
+            message => Err(NetError::Protocol(format!(
+                "expected game, got '{}'",
+                message
+            ))),
+        }
+    }
+
+    /// Exchanges `chess` lines and turns away another protocol version.
+    fn handshake(&self) -> Result<(), NetError> {
+        self.send(&NetMessage::Hello {
+            version: PROTOCOL_VERSION,
+        })?;
+        match self.recv(HANDSHAKE_TIMEOUT)? {
+            NetMessage::Hello { version } if version == PROTOCOL_VERSION => Ok(()),
+            NetMessage::Hello { version } => {
+                let _ = self.send(&NetMessage::Error(NetError::Version(version).to_string()));
+                Err(NetError::Version(version))
+            }
+            message => Err(NetError::Protocol(format!(
+                "expected chess, got '{}'",
+                message
+            ))),
+        }
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    pub fn send(&self, message: &NetMessage) -> io::Result<()> {
+        let mut stream = &self.stream;
+        writeln!(stream, "{}", message)?;
+        stream.flush()
+    }
+
+    /// The next message if one has arrived. An `error` from the other end
+    /// is returned as `NetError::Refused`.
+    pub fn try_recv(&self) -> Result<Option<NetMessage>, NetError> {
+        let received = self.lines.lock().unwrap().try_recv();
+        match received {
+            Ok(line) => parse_received(&line).map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(NetError::Closed),
+        }
+    }
+
+    /// Waits up to `timeout` for the next message.
+    pub fn recv(&self, timeout: Duration) -> Result<NetMessage, NetError> {
+        let received = self.lines.lock().unwrap().recv_timeout(timeout);
+        match received {
+            Ok(line) => parse_received(&line),
+            Err(RecvTimeoutError::Timeout) => Err(NetError::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(NetError::Closed),
+        }
+    }
+}
+
+fn parse_received(line: &str) -> Result<NetMessage, NetError> {
+    match NetMessage::parse(line).map_err(NetError::Protocol)? {
+        NetMessage::Error(text) => Err(NetError::Refused(text)),
+        message => Ok(message),
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.send(&NetMessage::Quit);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// A connection that completed the handshake, with what it came for.
+pub type Joined = Result<(Connection, Role), NetError>;
+
+/// Listens for players and spectators to connect to a hosted game.
+///
+/// Connections are accepted and greeted on separate threads, `accept` and
+/// `try_accept` hand out those that completed the handshake. The host sends
+/// them the `game`.
+pub struct NetHost {
+    address: SocketAddr,
+    connections: Mutex<Receiver<Joined>>,
+}
+
+impl NetHost {
+    /// Listens on `address`, e.g. `0.0.0.0:7878`, or port 0 for any free one.
+    pub fn bind(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let sender = sender.clone();
+                // A slow handshake doesn't hold up the next player
+                thread::spawn(move || {
+                    let _ = sender.send(greet(stream));
+                });
+            }
+        });
+        Ok(NetHost {
+            address,
+            connections: Mutex::new(receiver),
+        })
+    }
+
+    /// The address actually listened on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Waits for the next player or spectator to connect. Failed handshakes
+    /// are returned as errors as well.
+    pub fn accept(&self) -> Joined {
+        self.connections
+            .lock()
+            .unwrap()
+            .recv()
+            .map_err(|_| NetError::Closed)?
+    }
+
+    /// The next connection if one has completed the handshake.
+    pub fn try_accept(&self) -> Option<Joined> {
+        match self.connections.lock().unwrap().try_recv() {
+            Ok(joined) => Some(joined),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(NetError::Closed)),
+        }
+    }
+}
+
+fn greet(stream: io::Result<TcpStream>) -> Joined {
+    let connection = Connection::new(stream?)?;
+    connection.handshake()?;
+    match connection.recv(HANDSHAKE_TIMEOUT)? {
+        NetMessage::Join(role) => Ok((connection, role)),
+        message => Err(NetError::Protocol(format!(
+            "expected join, got '{}'",
+            message
+        ))),
+    }
+}
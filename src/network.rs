@@ -0,0 +1,402 @@
+use std::io;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, MakeMove, MovePlayed};
+use crate::clock::ClockSystem;
+use crate::net::{self, Connection, GameSetup, NetError, NetHost, NetMessage, Role};
+use crate::outcome::{Action, GameAction};
+use crate::rules::{Board, ChessClock, GameRecord, PieceColor};
+use crate::uci;
+use crate::MyGame;
+
+/// Seconds between updates of the host's camera sent to spectators.
+const CAMERA_INTERVAL: f32 = 0.1;
+
+pub fn init(app: &mut App) {
+    app.add_system(admit_spectators.before(BoardSystem::ApplyMoves))
+        .add_system(receive_messages.before(BoardSystem::ApplyMoves))
+        .add_system(send_moves.after(ClockSystem))
+        .add_system(send_actions)
+        .add_system(send_camera)
+        .add_system(follow_camera);
+}
+
+/// A game against the `PlayerKind::Remote` player on another instance, or
+/// watching two of them, see `net` for the protocol.
+///
+/// Each end plays the moves it receives through `MakeMove` and the rest
+/// through `GameAction`, so all keep the same game by the same rules.
+pub struct NetGame {
+    connection: Connection,
+    /// The side played here, `None` when watching.
+    pub color: Option<PieceColor>,
+    /// Ply of the last move received and the time its mover had left, put
+    /// on the clock once the move is played.
+    remote_clock: Option<(usize, Duration)>,
+    connected: bool,
+    /// The host's camera as last sent, see `MyGame::camera`.
+    host_camera: Option<Vec3>,
+    /// Whether a spectator's camera follows the host's. C switches it.
+    pub follow_camera: bool,
+}
+
+/// The game of `record` as sent to a player of `color` or a spectator.
+fn game_setup(color: Option<PieceColor>, record: &GameRecord) -> GameSetup {
+    GameSetup {
+        color,
+        start: record.start.clone(),
+        moves: record.moves.iter().map(|recorded| recorded.mv).collect(),
+        control: record
+            .tag("TimeControl")
+            .and_then(|control| control.parse().ok()),
+    }
+}
+
+impl NetGame {
+    fn new(connection: Connection, color: Option<PieceColor>) -> Self {
+        NetGame {
+            connection,
+            color,
+            remote_clock: None,
+            connected: true,
+            host_camera: None,
+            follow_camera: color.is_none(),
+        }
+    }
+
+    /// Starts the game of `record` with the player on `connection`, who gets
+    /// the side other than `color`.
+    pub fn host(
+        connection: Connection,
+        color: PieceColor,
+        record: &GameRecord,
+    ) -> io::Result<Self> {
+        let setup = game_setup(Some(color.opponent()), record);
+        connection.send(&NetMessage::Game(Box::new(setup)))?;
+        Ok(NetGame::new(connection, Some(color)))
+    }
+
+    /// Joins the game hosted at `address`, on `net::DEFAULT_PORT` unless it
+    /// has a port, to play or watch.
+    pub fn join(address: &str, role: Role) -> Result<(Self, GameSetup), NetError> {
+        let (connection, setup) = Connection::connect(&net::with_default_port(address), role)?;
+        Ok((NetGame::new(connection, setup.color), setup))
+    }
+
+    fn send(&mut self, message: &NetMessage) {
+        if !self.connected {
+            return;
+        }
+        debug!("net > {}", message);
+        if let Err(err) = self.connection.send(message) {
+            error!("failed to send to opponent: {}", err);
+            self.connected = false;
+        }
+    }
+}
+
+/// Those watching a hosted game. They get the moves so far when they join
+/// and then every move and action as it is played. Anything they send other
+/// than `quit` is ignored.
+pub struct Spectators {
+    host: NetHost,
+    watching: Vec<Connection>,
+}
+
+impl Spectators {
+    pub fn new(host: NetHost) -> Self {
+        Spectators {
+            host,
+            watching: Vec::new(),
+        }
+    }
+
+    /// Waits for the next player or spectator to connect, see
+    /// `NetHost::accept`.
+    pub fn accept(&self) -> net::Joined {
+        self.host.accept()
+    }
+
+    /// Sends the game so far to a spectator who just joined, and the clocks
+    /// and camera if there are any yet.
+    pub fn admit(
+        &mut self,
+        connection: Connection,
+        record: &GameRecord,
+        clock: Option<&ChessClock>,
+        camera: Option<Vec3>,
+    ) {
+        let mut messages = vec![NetMessage::Game(Box::new(game_setup(None, record)))];
+        if let Some(clock) = clock {
+            messages.push(NetMessage::Clock {
+                white: clock.remaining(PieceColor::White),
+                black: clock.remaining(PieceColor::Black),
+            });
+        }
+        if let Some(camera) = camera {
+            messages.push(NetMessage::Camera(camera.to_array()));
+        }
+        let sent = messages
+            .iter()
+            .try_for_each(|message| connection.send(message));
+        if let Err(err) = sent {
+            warn!("failed to send the game to a spectator: {}", err);
+            return;
+        }
+        if let Ok(peer) = connection.peer_addr() {
+            info!("{} is watching", peer);
+        }
+        self.watching.push(connection);
+    }
+
+    /// Sends `message` to everyone watching, dropping those who are gone.
+    fn broadcast(&mut self, message: &NetMessage) {
+        self.watching
+            .retain(|connection| connection.send(message).is_ok());
+    }
+}
+
+/// Whether a spectator is still there, after reading what it sent.
+fn still_watching(connection: &Connection) -> bool {
+    loop {
+        match connection.try_recv() {
+            Ok(None) => return true,
+            Ok(Some(NetMessage::Quit)) | Err(NetError::Closed | NetError::Refused(_)) => {
+                return false
+            }
+            Ok(Some(_)) | Err(_) => {}
+        }
+    }
+}
+
+/// Lets spectators join a hosted game at any time. Runs before the moves of
+/// the frame are played, which are then passed on to them like the rest.
+fn admit_spectators(
+    spectators: Option<ResMut<Spectators>>,
+    record: Res<GameRecord>,
+    clock: Option<Res<ChessClock>>,
+    game: Res<MyGame>,
+) {
+    let mut spectators = match spectators {
+        Some(spectators) => spectators,
+        None => return,
+    };
+    while let Some(joined) = spectators.host.try_accept() {
+        match joined {
+            Ok((connection, Role::Spectator)) => {
+                spectators.admit(connection, &record, clock.as_deref(), Some(game.camera))
+            }
+            Ok((connection, Role::Player)) => {
+                let refusal = NetMessage::Error("the game already has two players".to_string());
+                let _ = connection.send(&refusal);
+            }
+            Err(NetError::Closed) => break,
+            Err(err) => warn!("a spectator failed to connect: {}", err),
+        }
+    }
+    spectators.watching.retain(still_watching);
+}
+
+/// Plays the moves received and passes on resignations and draw offers. The
+/// mover's clock is set to the time it reported once its move is played.
+fn receive_messages(
+    net_game: Option<ResMut<NetGame>>,
+    board: Res<Board>,
+    mut record: ResMut<GameRecord>,
+    mut clock: Option<ResMut<ChessClock>>,
+    mut make_move: EventWriter<MakeMove>,
+    mut actions: EventWriter<GameAction>,
+) {
+    let mut game = match net_game {
+        Some(game) => game,
+        None => return,
+    };
+    let game = &mut *game;
+
+    if let Some((ply, remaining)) = game.remote_clock {
+        if ply < record.moves.len() {
+            record.moves[ply].clock = Some(remaining);
+            if let Some(clock) = clock.as_mut() {
+                clock.set_remaining(record.board_at(ply).side_to_move, remaining);
+            }
+            game.remote_clock = None;
+        }
+    }
+
+    while game.connected {
+        let message = match game.connection.try_recv() {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(NetError::Closed) => {
+                error!("lost the connection to the game");
+                game.connected = false;
+                break;
+            }
+            Err(NetError::Refused(text)) => {
+                error!("the other end hung up: {}", text);
+                game.connected = false;
+                break;
+            }
+            Err(err) => {
+                warn!("{}", err);
+                continue;
+            }
+        };
+        debug!("net < {}", message);
+
+        let (color, action) = match message {
+            NetMessage::Move { mv, clock: left } => {
+                if Some(board.side_to_move) == game.color {
+                    warn!("opponent moved out of turn: {}", mv);
+                    continue;
+                }
+                match uci::parse_move(&board, &mv) {
+                    Some(mv) => {
+                        make_move.send(MakeMove(mv));
+                        let ply = record.moves.len();
+                        game.remote_clock = left.map(|remaining| (ply, remaining));
+                    }
+                    None => error!("illegal move {} received", mv),
+                }
+                // The next one is read once this one is on the board
+                break;
+            }
+            NetMessage::Clock { white, black } => {
+                if let Some(clock) = clock.as_mut() {
+                    clock.set_remaining(PieceColor::White, white);
+                    clock.set_remaining(PieceColor::Black, black);
+                }
+                continue;
+            }
+            NetMessage::Camera(camera) => {
+                game.host_camera = Some(Vec3::from(camera));
+                continue;
+            }
+            NetMessage::Resign(color) => (color, Action::Resign),
+            NetMessage::OfferDraw(color) => (color, Action::OfferDraw),
+            NetMessage::AcceptDraw(color) => (color, Action::AcceptDraw),
+            NetMessage::DeclineDraw(color) => (color, Action::DeclineDraw),
+            NetMessage::Quit => {
+                info!("the other end left the game");
+                game.connected = false;
+                continue;
+            }
+            NetMessage::Other(_) => continue,
+            message => {
+                warn!("unexpected message: {}", message);
+                continue;
+            }
+        };
+        if Some(color) == game.color {
+            warn!("ignoring {:?} for our side", action);
+        } else {
+            actions.send(GameAction { color, action });
+        }
+    }
+}
+
+/// Sends the moves played here to the opponent, with the time left after
+/// them, and all moves to spectators. Runs once the clock has been pressed.
+fn send_moves(
+    net_game: Option<ResMut<NetGame>>,
+    mut spectators: Option<ResMut<Spectators>>,
+    record: Res<GameRecord>,
+    clock: Option<Res<ChessClock>>,
+    mut played: EventReader<MovePlayed>,
+) {
+    let mut game = match net_game {
+        Some(game) => game,
+        None => return,
+    };
+    for event in played.iter() {
+        // The opponent's own time for its moves, ours for the rest
+        let remaining = match game.remote_clock {
+            Some((ply, remaining)) if ply == event.ply => Some(remaining),
+            _ => clock.as_ref().map(|clock| clock.remaining(event.color)),
+        };
+        let before = record.board_at(event.ply);
+        let message = NetMessage::Move {
+            mv: uci::move_text(&before, event.mv),
+            clock: remaining,
+        };
+        if Some(event.color) == game.color {
+            game.send(&message);
+        }
+        if let Some(spectators) = spectators.as_mut() {
+            spectators.broadcast(&message);
+        }
+    }
+}
+
+/// Passes on resignations and draw offers made here to the opponent, and
+/// all of them to spectators.
+fn send_actions(
+    net_game: Option<ResMut<NetGame>>,
+    mut spectators: Option<ResMut<Spectators>>,
+    mut actions: EventReader<GameAction>,
+) {
+    let mut game = match net_game {
+        Some(game) => game,
+        None => return,
+    };
+    for &GameAction { color, action } in actions.iter() {
+        let message = match action {
+            Action::Resign => NetMessage::Resign(color),
+            Action::OfferDraw => NetMessage::OfferDraw(color),
+            Action::AcceptDraw => NetMessage::AcceptDraw(color),
+            Action::DeclineDraw => NetMessage::DeclineDraw(color),
+        };
+        if Some(color) == game.color {
+            game.send(&message);
+        }
+        if let Some(spectators) = spectators.as_mut() {
+            spectators.broadcast(&message);
+        }
+    }
+}
+
+/// Sends the host's camera to spectators when it has moved, a few times a
+/// second at most.
+fn send_camera(
+    time: Res<Time>,
+    game: Res<MyGame>,
+    spectators: Option<ResMut<Spectators>>,
+    mut since_sent: Local<f32>,
+    mut sent: Local<Option<Vec3>>,
+) {
+    let mut spectators = match spectators {
+        Some(spectators) => spectators,
+        None => return,
+    };
+    *since_sent += time.delta_seconds();
+    if *since_sent < CAMERA_INTERVAL || *sent == Some(game.camera) {
+        return;
+    }
+    *since_sent = 0.0;
+    *sent = Some(game.camera);
+    spectators.broadcast(&NetMessage::Camera(game.camera.to_array()));
+}
+
+/// Turns the camera of a spectator following the host with it. C switches
+/// following on and off.
+fn follow_camera(
+    input: Res<Input<KeyCode>>,
+    net_game: Option<ResMut<NetGame>>,
+    mut game: ResMut<MyGame>,
+) {
+    let mut net_game = match net_game {
+        Some(net_game) if net_game.color.is_none() => net_game,
+        _ => return,
+    };
+    if input.just_pressed(KeyCode::C) {
+        net_game.follow_camera = !net_game.follow_camera;
+        info!("following the host's camera: {}", net_game.follow_camera);
+    }
+    if let Some(camera) = net_game.host_camera.filter(|_| net_game.follow_camera) {
+        if game.camera != camera {
+            game.camera = camera;
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, MovePlayed};
+use crate::player::{PlayerKind, Players};
+use crate::rules::{Board, GameRecord, GameResult, Outcome, PieceColor, Termination};
+
+pub fn init(app: &mut App) {
+    app.init_resource::<DrawOffer>()
+        .add_event::<GameAction>()
+        .add_event::<GameOver>()
+        .add_system(detect_game_end.after(BoardSystem::ApplyMoves))
+        .add_system(handle_actions.after(BoardSystem::ApplyMoves))
+        .add_system(action_keys);
+}
+
+/// Sent once when the game ends, however it ended.
+pub struct GameOver(pub Outcome);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Resign,
+    /// Offers a draw, or accepts the opponent's open offer. Claims the draw
+    /// right away if the rules allow it.
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+}
+
+/// A player doing something other than moving.
+pub struct GameAction {
+    pub color: PieceColor,
+    pub action: Action,
+}
+
+/// The side with an open draw offer. It lapses when the opponent moves.
+#[derive(Default)]
+pub struct DrawOffer(pub Option<PieceColor>);
+
+/// Ends the game in the `GameRecord` and announces it with `GameOver`.
+/// Does nothing if the game is already over.
+pub fn finish(record: &mut GameRecord, outcome: Outcome, game_over: &mut EventWriter<GameOver>) {
+    if record.result != GameResult::Ongoing {
+        return;
+    }
+    record.result = outcome.result;
+    let termination = match outcome.termination {
+        Termination::TimeForfeit => "time forfeit",
+        _ => "normal",
+    };
+    record.set_tag("Termination", termination);
+    info!(
+        "game over: {} by {}",
+        outcome.result.as_str(),
+        outcome.termination.description()
+    );
+    game_over.send(GameOver(outcome));
+}
+
+/// Checkmate, stalemate and the draws the rules enforce without a claim.
+fn detect_game_end(
+    board: Res<Board>,
+    mut record: ResMut<GameRecord>,
+    mut game_over: EventWriter<GameOver>,
+) {
+    if !board.is_changed() || record.result != GameResult::Ongoing {
+        return;
+    }
+    if let Some(outcome) = record.automatic_outcome(&board) {
+        finish(&mut record, outcome, &mut game_over);
+    }
+}
+
+fn handle_actions(
+    board: Res<Board>,
+    mut record: ResMut<GameRecord>,
+    mut offer: ResMut<DrawOffer>,
+    mut actions: EventReader<GameAction>,
+    mut played: EventReader<MovePlayed>,
+    mut game_over: EventWriter<GameOver>,
+) {
+    for event in played.iter() {
+        if offer.0.map_or(false, |color| color != event.color) {
+            offer.0 = None;
+        }
+    }
+
+    for &GameAction { color, action } in actions.iter() {
+        if record.result != GameResult::Ongoing {
+            break;
+        }
+        let offered_to_us = offer.0 == Some(color.opponent());
+        match action {
+            Action::Resign => finish(
+                &mut record,
+                Outcome::win(color.opponent(), Termination::Resignation),
+                &mut game_over,
+            ),
+            Action::OfferDraw | Action::AcceptDraw => {
+                if let Some(outcome) = record.claimable_draw(&board) {
+                    finish(&mut record, outcome, &mut game_over);
+                } else if offered_to_us {
+                    finish(
+                        &mut record,
+                        Outcome::draw(Termination::DrawAgreement),
+                        &mut game_over,
+                    );
+                } else if action == Action::OfferDraw {
+                    info!("{:?} offers a draw", color);
+                    offer.0 = Some(color);
+                }
+            }
+            Action::DeclineDraw => {
+                if offered_to_us {
+                    info!("{:?} declines the draw", color);
+                    offer.0 = None;
+                }
+            }
+        }
+    }
+}
+
+/// O offers, accepts or claims a draw, N declines one and Shift+R resigns,
+/// for whichever side is played by a human.
+fn action_keys(
+    input: Res<Input<KeyCode>>,
+    board: Res<Board>,
+    players: Res<Players>,
+    offer: Res<DrawOffer>,
+    mut actions: EventWriter<GameAction>,
+) {
+    let human = |color: PieceColor| players.get(color) == PlayerKind::Human;
+    // The side to move if it is human, otherwise the human opponent
+    let acting = [board.side_to_move, board.side_to_move.opponent()]
+        .into_iter()
+        .find(|&color| human(color));
+    let answering = offer
+        .0
+        .map(PieceColor::opponent)
+        .filter(|&color| human(color));
+
+    let mut send = |color: Option<PieceColor>, action: Action| {
+        if let Some(color) = color {
+            actions.send(GameAction { color, action });
+        }
+    };
+    if input.just_pressed(KeyCode::O) {
+        match answering {
+            Some(color) => send(Some(color), Action::AcceptDraw),
+            None => send(acting, Action::OfferDraw),
+        }
+    }
+    if input.just_pressed(KeyCode::N) {
+        send(answering, Action::DeclineDraw);
+    }
+    if input.pressed(KeyCode::LShift) && input.just_pressed(KeyCode::R) {
+        send(acting, Action::Resign);
+    }
+}
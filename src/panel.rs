@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::history::{GoToPly, History};
+use crate::picking::Selection;
+use crate::rules::{
+    format_clock, piece_letter, Board, ChessClock, GameRecord, GameResult, PieceColor, PieceType,
+    VariantKind, DROP_PIECES,
+};
+
+pub fn init(app: &mut App) {
+    app.add_system(side_panel);
+}
+
+/// Conventional pawn units on `board`, for the material balance.
+fn material_value(board: &Board, ptype: PieceType) -> i32 {
+    match ptype {
+        PieceType::King => 0,
+        PieceType::Pawn => 1,
+        PieceType::Knight | PieceType::Bishop => 3,
+        PieceType::Rook => 5,
+        PieceType::Queen => 9,
+        PieceType::Fairy(_) => board
+            .fairy(ptype)
+            .map_or(0, |fairy| (fairy.value + 50) / 100),
+    }
+}
+
+/// Pieces of each color captured so far in `record`, most valuable first.
+fn captured_pieces(record: &GameRecord) -> [Vec<PieceType>; 2] {
+    let mut captured = [Vec::new(), Vec::new()];
+    for &(ptype, color) in record.moves.iter().flat_map(|recorded| &recorded.captured) {
+        captured[color as usize].push(ptype);
+    }
+    for pieces in &mut captured {
+        pieces.sort_by_key(|&ptype| -material_value(&record.start, ptype));
+    }
+    captured
+}
+
+/// White's material minus Black's, in pawns.
+fn material_balance(board: &Board) -> i32 {
+    board
+        .pieces()
+        .map(|(_, ptype, color)| match color {
+            PieceColor::White => material_value(board, ptype),
+            PieceColor::Black => -material_value(board, ptype),
+        })
+        .sum()
+}
+
+fn status(board: &Board, record: &GameRecord) -> String {
+    match record.result {
+        GameResult::Ongoing => {
+            let side = match board.side_to_move {
+                PieceColor::White => "White",
+                PieceColor::Black => "Black",
+            };
+            if board.in_check(board.side_to_move) {
+                format!("{} to move, in check", side)
+            } else {
+                format!("{} to move", side)
+            }
+        }
+        result => format!("Game over: {}", result.as_str()),
+    }
+}
+
+/// Game info on the right: whose turn it is, the variant, clocks, material
+/// and the move list. Clicking a move shows the position after it, clicking
+/// a Crazyhouse pocket piece selects it for dropping.
+fn side_panel(
+    mut egui_context: ResMut<EguiContext>,
+    board: Res<Board>,
+    record: Res<GameRecord>,
+    history: Res<History>,
+    clock: Option<Res<ChessClock>>,
+    mut selection: ResMut<Selection>,
+    mut go_to: EventWriter<GoToPly>,
+) {
+    let captured = captured_pieces(&record);
+    let balance = material_balance(&board);
+    let ply = record.moves.len();
+
+    egui::SidePanel::right("game_panel")
+        .min_width(220.0)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading(status(&board, &record));
+            if history.browsing() {
+                ui.label(format!(
+                    "Viewing move {} of {}",
+                    ply,
+                    history.all_moves(&record).count()
+                ));
+            }
+            if board.variant != VariantKind::Standard {
+                ui.label(board.variant.name());
+            }
+            ui.separator();
+
+            for (color, name) in [(PieceColor::White, "White"), (PieceColor::Black, "Black")] {
+                ui.horizontal(|ui| {
+                    ui.strong(name);
+                    if let Some(clock) = &clock {
+                        ui.monospace(format_clock(clock.remaining(color)));
+                    }
+                    let advantage = match color {
+                        PieceColor::White => balance,
+                        PieceColor::Black => -balance,
+                    };
+                    if advantage > 0 {
+                        ui.label(format!("+{}", advantage));
+                    }
+                    if board.variant == VariantKind::ThreeCheck {
+                        let checks = board.variant_state.checks[color as usize];
+                        ui.label(format!("Checks: {}", checks));
+                    }
+                });
+                if board.variant == VariantKind::Crazyhouse {
+                    let can_drop = color == board.side_to_move
+                        && record.result == GameResult::Ongoing
+                        && !history.browsing();
+                    ui.horizontal(|ui| {
+                        ui.label("Pocket:");
+                        for ptype in DROP_PIECES {
+                            let count = board.variant_state.pocket(color, ptype);
+                            if count == 0 {
+                                continue;
+                            }
+                            let letter = piece_letter(ptype, board.fairy_pieces()).unwrap_or('P');
+                            let selected = can_drop && selection.drop == Some(ptype);
+                            let button = ui.add_enabled(
+                                can_drop,
+                                egui::SelectableLabel::new(
+                                    selected,
+                                    format!("{}x{}", letter, count),
+                                ),
+                            );
+                            if button.clicked() {
+                                selection.square = None;
+                                selection.drop = if selected { None } else { Some(ptype) };
+                            }
+                        }
+                    });
+                }
+                // Pieces this side took from the opponent
+                let taken: Vec<String> = captured[color.opponent() as usize]
+                    .iter()
+                    .map(|&ptype| {
+                        piece_letter(ptype, board.fairy_pieces())
+                            .unwrap_or('P')
+                            .to_string()
+                    })
+                    .collect();
+                ui.label(format!("Captured: {}", taken.join(" ")));
+            }
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if ui.selectable_label(ply == 0, "Start").clicked() {
+                    go_to.send(GoToPly(0));
+                }
+                egui::Grid::new("move_list")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let start = &record.start;
+                        let mut number = start.fullmove_number;
+                        if start.side_to_move == PieceColor::Black {
+                            ui.label(format!("{}.", number));
+                            ui.label("...");
+                        }
+                        for (i, recorded) in history.all_moves(&record).enumerate() {
+                            let white = (i % 2 == 0) == (start.side_to_move == PieceColor::White);
+                            if white {
+                                ui.label(format!("{}.", number));
+                            }
+                            if ui.selectable_label(i + 1 == ply, &recorded.san).clicked() {
+                                go_to.send(GoToPly(i + 1));
+                            }
+                            if !white {
+                                ui.end_row();
+                                number += 1;
+                            }
+                        }
+                    });
+            });
+        });
+}
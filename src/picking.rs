@@ -0,0 +1,266 @@
+use bevy::{prelude::*, render::camera::CameraPlugin};
+
+use crate::board::MakeMove;
+use crate::player::{PlayerKind, Players};
+use crate::promotion::PendingPromotion;
+use crate::rules::{Board, BoardSize, Move, MoveKind, PieceType, Square};
+
+pub fn init(app: &mut App) {
+    app.init_resource::<Selection>().add_system(pick_square);
+}
+
+/// The square whose piece was selected with the first click, if any, or
+/// the piece picked from the pocket to drop in Crazyhouse.
+#[derive(Default)]
+pub struct Selection {
+    pub square: Option<Square>,
+    pub drop: Option<PieceType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+/// Casts a ray from the camera through `cursor`, given in window coordinates
+/// with the origin in the bottom left corner.
+pub fn ray_from_cursor(
+    cursor: Vec2,
+    window_size: Vec2,
+    camera_transform: &GlobalTransform,
+    projection: Mat4,
+) -> Ray {
+    let ndc = (cursor / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * projection.inverse();
+    // The perspective projection uses reversed depth, so z = 1 is the near plane
+    let near = ndc_to_world.project_point3(ndc.extend(1.0));
+    let further = ndc_to_world.project_point3(ndc.extend(0.5));
+    Ray {
+        origin: near,
+        direction: (further - near).normalize(),
+    }
+}
+
+/// Where `ray` hits the board plane `y = 0`, if it points towards it.
+pub fn intersect_board_plane(ray: Ray) -> Option<Vec3> {
+    if ray.direction.y.abs() < f32::EPSILON {
+        return None;
+    }
+    let distance = -ray.origin.y / ray.direction.y;
+    if distance < 0.0 {
+        return None;
+    }
+    Some(ray.origin + ray.direction * distance)
+}
+
+/// The square containing `point` on the plane of a board of `size`.
+/// Inverse of `piece::square_translation`.
+pub fn square_at(point: Vec3, size: BoardSize) -> Option<Square> {
+    let rank = (point.x + 0.5).floor();
+    let file = (point.z + 0.5).floor();
+    if (0.0..size.ranks as f32).contains(&rank) && (0.0..size.files as f32).contains(&file) {
+        Some(Square::new(file as u8, rank as u8))
+    } else {
+        None
+    }
+}
+
+/// The legal moves from `from` to `to`: one, or one per piece to promote to.
+/// Castling can also be picked on the rook's square, and only there when
+/// the king could step to `to` anyway, as can happen in Chess960.
+pub fn moves_between(board: &Board, from: Square, to: Square) -> Vec<Move> {
+    let moves = board.legal_moves_from(from);
+    let king_step = moves.iter().any(|mv| mv.to == to && !mv.is_castle());
+    moves
+        .into_iter()
+        .filter(|&mv| {
+            if mv.is_castle() {
+                let (rook, _) = board.castling_rook_squares(mv);
+                rook == to || (mv.to == to && !king_step)
+            } else {
+                mv.to == to
+            }
+        })
+        .collect()
+}
+
+/// Left click selects a piece of the side to move, a second left click on
+/// one of its destinations plays the move, or asks which piece to promote
+/// to. With a pocket piece selected the click drops it instead. Only while
+/// a human is to move.
+fn pick_square(
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    cameras: Query<(&GlobalTransform, &Camera)>,
+    board: Res<Board>,
+    players: Res<Players>,
+    mut selection: ResMut<Selection>,
+    mut promotion: ResMut<PendingPromotion>,
+    mut make_move: EventWriter<MakeMove>,
+) {
+    if players.get(board.side_to_move) != PlayerKind::Human {
+        if selection.square.is_some() || selection.drop.is_some() {
+            selection.square = None;
+            selection.drop = None;
+        }
+        return;
+    }
+    if !mouse.just_pressed(MouseButton::Left) || promotion.0.is_some() {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let cursor = match window.cursor_position() {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    let clicked = cameras
+        .iter()
+        .find(|(_, camera)| camera.name == Some(CameraPlugin::CAMERA_3D.to_string()))
+        .and_then(|(transform, camera)| {
+            let window_size = Vec2::new(window.width(), window.height());
+            let ray = ray_from_cursor(cursor, window_size, transform, camera.projection_matrix);
+            intersect_board_plane(ray)
+        })
+        .and_then(|point| square_at(point, board.size()));
+
+    let clicked = match clicked {
+        Some(square) => square,
+        None => {
+            selection.square = None;
+            selection.drop = None;
+            return;
+        }
+    };
+
+    if let Some(ptype) = selection.drop.take() {
+        let mv = Move::new(clicked, clicked, MoveKind::Drop(ptype));
+        if board.is_legal(mv) {
+            make_move.send(MakeMove(mv));
+            return;
+        }
+    }
+
+    if let Some(from) = selection.square {
+        match moves_between(&board, from, clicked).as_slice() {
+            [] => {}
+            [mv] => {
+                make_move.send(MakeMove(*mv));
+                selection.square = None;
+                return;
+            }
+            _ => {
+                promotion.0 = Some((from, clicked));
+                selection.square = None;
+                return;
+            }
+        }
+    }
+
+    selection.square = match board.get(clicked) {
+        Some((_, color)) if color == board.side_to_move && selection.square != Some(clicked) => {
+            Some(clicked)
+        }
+        _ => None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::square_translation;
+
+    fn window_size() -> Vec2 {
+        Vec2::new(1280., 720.)
+    }
+
+    /// A camera behind White looking down at the middle of the board, with
+    /// the projection of the default 3D camera.
+    fn camera() -> (GlobalTransform, Mat4) {
+        let transform = Transform::from_translation(Vec3::new(-4., 9., 3.5))
+            .looking_at(Vec3::new(3.5, 0., 3.5), Vec3::Y);
+        let aspect = window_size().x / window_size().y;
+        let projection =
+            Mat4::perspective_infinite_reverse_rh(std::f32::consts::FRAC_PI_4, aspect, 0.1);
+        (GlobalTransform::from(transform), projection)
+    }
+
+    /// Where `point` shows up in the window.
+    fn cursor_at(point: Vec3, transform: &GlobalTransform, projection: Mat4) -> Vec2 {
+        let clip = projection * transform.compute_matrix().inverse() * point.extend(1.);
+        let ndc = Vec2::new(clip.x, clip.y) / clip.w;
+        (ndc + Vec2::ONE) / 2. * window_size()
+    }
+
+    #[test]
+    fn square_centres_round_trip() {
+        for size in [BoardSize::STANDARD, BoardSize::new(6, 6).unwrap()] {
+            for square in size.squares() {
+                let centre = square_translation(square);
+                assert_eq!(square_at(centre, size), Some(square));
+                let corner = centre + Vec3::new(0.45, 0., -0.45);
+                assert_eq!(square_at(corner, size), Some(square));
+            }
+        }
+    }
+
+    #[test]
+    fn clicks_round_trip() {
+        let (transform, projection) = camera();
+        for square in BoardSize::STANDARD.squares() {
+            let centre = square_translation(square);
+            let cursor = cursor_at(centre, &transform, projection);
+            let ray = ray_from_cursor(cursor, window_size(), &transform, projection);
+            let hit = intersect_board_plane(ray).unwrap();
+            assert!(hit.distance(centre) < 1e-3, "{} hit at {}", square, hit);
+            assert_eq!(square_at(hit, BoardSize::STANDARD), Some(square));
+        }
+    }
+
+    #[test]
+    fn off_the_board() {
+        let size = BoardSize::STANDARD;
+        assert_eq!(square_at(Vec3::new(-0.6, 0., 3.), size), None);
+        assert_eq!(square_at(Vec3::new(3., 0., -0.6), size), None);
+        assert_eq!(square_at(Vec3::new(7.6, 0., 3.), size), None);
+        assert_eq!(square_at(Vec3::new(3., 0., 7.6), size), None);
+        // On the 8x8 board, but past the last file and rank of 6x6
+        assert_eq!(
+            square_at(Vec3::new(6., 0., 2.), BoardSize::new(6, 6).unwrap()),
+            None
+        );
+        assert_eq!(
+            square_at(Vec3::new(2., 0., 6.), BoardSize::new(6, 6).unwrap()),
+            None
+        );
+
+        // A click above the horizon misses the plane altogether
+        let (transform, projection) = camera();
+        let sky = cursor_at(Vec3::new(100., 50., 3.5), &transform, projection);
+        let ray = ray_from_cursor(sky, window_size(), &transform, projection);
+        assert_eq!(intersect_board_plane(ray), None);
+    }
+
+    #[test]
+    fn rays_missing_the_plane() {
+        let parallel = Ray {
+            origin: Vec3::new(0., 1., 0.),
+            direction: Vec3::X,
+        };
+        assert_eq!(intersect_board_plane(parallel), None);
+        let away = Ray {
+            origin: Vec3::new(0., 1., 0.),
+            direction: Vec3::new(0.3, 1., 0.).normalize(),
+        };
+        assert_eq!(intersect_board_plane(away), None);
+        let down = Ray {
+            origin: Vec3::new(1., 2., 3.),
+            direction: -Vec3::Y,
+        };
+        assert_eq!(intersect_board_plane(down), Some(Vec3::new(1., 0., 3.)));
+    }
+}
@@ -1,216 +1,167 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-pub enum PieceType {
-    King,
-    Pawn,
-    Knight,
-    Rook,
-    Bishop,
-    Queen,
-}
+use std::collections::HashMap;
 
-pub enum PieceColor {
-    Black,
-    White,
-}
+use crate::piece_set::PieceSet;
+use crate::rules::{self, BoardSize, Square};
+pub use crate::rules::{PieceColor, PieceType};
 
+/// Marks the parent entity of a spawned piece and the square it stands on.
+/// Kept in line with the logical `Board` by `board::sync_pieces`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Piece {
     pub ptype: PieceType,
     pub color: PieceColor,
-    pub x: f32,
-    pub y: f32,
+    pub square: Square,
+}
+
+/// World position of the center of `square`.
+pub fn square_translation(square: Square) -> Vec3 {
+    Vec3::new(square.rank as f32, 0., square.file as f32)
+}
+
+/// World position of the middle of a board of `size`, which the camera
+/// looks at.
+pub fn board_center(size: BoardSize) -> Vec3 {
+    Vec3::new(
+        (size.ranks as f32 - 1.) / 2.,
+        0.,
+        (size.files as f32 - 1.) / 2.,
+    )
+}
+
+/// How a fairy piece is shown: with the model of a standard piece and,
+/// unless it has no tint, a material of its own.
+#[derive(Debug, Clone, Copy)]
+pub struct FairyLook {
+    pub model: PieceType,
+    pub tint: Option<Color>,
+}
+
+/// Looks of the fairy pieces, from the `model` and `tint` keys of the
+/// pieces file.
+#[derive(Debug, Default)]
+pub struct FairyLooks(pub HashMap<PieceType, FairyLook>);
+
+#[derive(Deserialize)]
+struct PiecesFile {
+    #[serde(default)]
+    piece: Vec<LookDefinition>,
+}
+
+#[derive(Deserialize)]
+struct LookDefinition {
+    name: String,
+    letter: char,
+    model: String,
+    tint: Option<[f32; 3]>,
+}
+
+impl FairyLooks {
+    /// Reads the looks of a pieces file whose pieces are already defined
+    /// with `rules::define_fairy_pieces`.
+    pub fn parse(toml: &str) -> Result<FairyLooks, String> {
+        let file: PiecesFile = toml::from_str(toml).map_err(|err| err.to_string())?;
+        let mut looks = HashMap::new();
+        for def in file.piece {
+            let ptype = rules::fairy_piece(def.letter)
+                .ok_or_else(|| format!("{}: not defined", def.name))?;
+            let model = match def.model.as_str() {
+                "king" => PieceType::King,
+                "pawn" => PieceType::Pawn,
+                "knight" => PieceType::Knight,
+                "rook" => PieceType::Rook,
+                "bishop" => PieceType::Bishop,
+                "queen" => PieceType::Queen,
+                _ => return Err(format!("{}: unknown model '{}'", def.name, def.model)),
+            };
+            let tint = def.tint.map(|[r, g, b]| Color::rgb(r, g, b));
+            looks.insert(ptype, FairyLook { model, tint });
+        }
+        Ok(FairyLooks(looks))
+    }
+}
+
+/// `base` mixed half and half with `tint`, for tinted fairy pieces.
+pub fn tinted(base: Color, tint: Color) -> Color {
+    Color::rgb(
+        (base.r() + tint.r()) / 2.,
+        (base.g() + tint.g()) / 2.,
+        (base.b() + tint.b()) / 2.,
+    )
+}
+
+/// Meshes of a standard piece and where they go relative to its square,
+/// taken from the piece set.
+#[derive(Debug, Clone)]
+pub struct PieceParts {
+    pub meshes: Vec<Handle<Mesh>>,
+    pub transform: Transform,
+}
+
+/// Mesh and material handles shared by all piece entities.
+pub struct PieceAssets {
+    /// The piece set in use, see `piece_set`. Chosen by `theme`.
+    pub set: Handle<PieceSet>,
+    /// Parts of each standard piece, empty until `set` has loaded.
+    pub models: HashMap<PieceType, PieceParts>,
+    pub white_material: Handle<StandardMaterial>,
+    pub black_material: Handle<StandardMaterial>,
+    pub fairy_looks: HashMap<PieceType, FairyLook>,
+    /// Materials of tinted fairy pieces.
+    pub fairy_materials: HashMap<(PieceType, PieceColor), Handle<StandardMaterial>>,
 }
 
-#[rustfmt::skip]
-pub const BASE_PIECES : &'static [&'static Piece] = &[
-    &Piece{ptype: PieceType::Rook,   color: PieceColor::White, x: 0., y: 0.},
-    &Piece{ptype: PieceType::Knight, color: PieceColor::White, x: 0., y: 1.},
-    &Piece{ptype: PieceType::Bishop, color: PieceColor::White, x: 0., y: 2.},
-    &Piece{ptype: PieceType::Queen,  color: PieceColor::White, x: 0., y: 3.},
-    &Piece{ptype: PieceType::King,   color: PieceColor::White, x: 0., y: 4.},
-    &Piece{ptype: PieceType::Bishop, color: PieceColor::White, x: 0., y: 5.},
-    &Piece{ptype: PieceType::Knight, color: PieceColor::White, x: 0., y: 6.},
-    &Piece{ptype: PieceType::Rook,   color: PieceColor::White, x: 0., y: 7.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 0.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 1.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 2.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 3.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 4.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 5.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 6.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::White, x: 1., y: 7.},
-    &Piece{ptype: PieceType::Rook,   color: PieceColor::Black, x: 7., y: 0.},
-    &Piece{ptype: PieceType::Knight, color: PieceColor::Black, x: 7., y: 1.},
-    &Piece{ptype: PieceType::Bishop, color: PieceColor::Black, x: 7., y: 2.},
-    &Piece{ptype: PieceType::Queen,  color: PieceColor::Black, x: 7., y: 3.},
-    &Piece{ptype: PieceType::King,   color: PieceColor::Black, x: 7., y: 4.},
-    &Piece{ptype: PieceType::Bishop, color: PieceColor::Black, x: 7., y: 5.},
-    &Piece{ptype: PieceType::Knight, color: PieceColor::Black, x: 7., y: 6.},
-    &Piece{ptype: PieceType::Rook,   color: PieceColor::Black, x: 7., y: 7.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 0.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 1.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 2.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 3.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 4.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 5.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 6.},
-    &Piece{ptype: PieceType::Pawn,   color: PieceColor::Black, x: 6., y: 7.},
-];
+impl PieceAssets {
+    /// The standard piece whose model `ptype` is shown with. Fairy pieces
+    /// without a look are shown as pawns.
+    pub fn model(&self, ptype: PieceType) -> PieceType {
+        match self.fairy_looks.get(&ptype) {
+            Some(look) => look.model,
+            None if matches!(ptype, PieceType::Fairy(_)) => PieceType::Pawn,
+            None => ptype,
+        }
+    }
+
+    pub fn material(&self, ptype: PieceType, color: PieceColor) -> &Handle<StandardMaterial> {
+        if let Some(material) = self.fairy_materials.get(&(ptype, color)) {
+            return material;
+        }
+        match color {
+            PieceColor::Black => &self.white_material,
+            PieceColor::White => &self.black_material,
+        }
+    }
+}
 
 impl Piece {
-    pub fn spawn(
-        commands: &mut Commands,
-        piece: &Piece,
-        meshes: &[&Handle<Mesh>],
-        material: &Handle<StandardMaterial>,
-    ) {
-        match piece.ptype {
-            PieceType::King => {
-                commands
-                    // Spawn parent entity
-                    .spawn_bundle(PbrBundle {
-                        transform: Transform::from_translation(Vec3::new(piece.x, 0., piece.y)),
-                        ..Default::default()
-                    })
-                    // Add children to the parent
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[0].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.2, 0., -1.9));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[1].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.2, 0., -1.9));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                    });
-            }
-            PieceType::Pawn => {
-                commands
-                    .spawn_bundle(PbrBundle {
-                        transform: Transform::from_translation(Vec3::new(piece.x, 0., piece.y)),
-                        ..Default::default()
-                    })
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[0].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.2, 0., 2.6));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                    });
-            }
-            PieceType::Knight => {
-                commands
-                    // Spawn parent entity
-                    .spawn_bundle(PbrBundle {
-                        transform: Transform::from_translation(Vec3::new(piece.x, 0., piece.y)),
-                        ..Default::default()
-                    })
-                    // Add children to the parent
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[0].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.2, 0., 0.9));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[1].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.2, 0., 0.9));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                    });
-            }
-            PieceType::Rook => {
-                commands
-                    .spawn_bundle(PbrBundle {
-                        transform: Transform::from_translation(Vec3::new(piece.x, 0., piece.y)),
-                        ..Default::default()
-                    })
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[0].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.1, 0., 1.8));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                    });
-            }
-            PieceType::Bishop => {
-                commands
-                    .spawn_bundle(PbrBundle {
-                        transform: Transform::from_translation(Vec3::new(piece.x, 0., piece.y)),
-                        ..Default::default()
-                    })
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[0].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.1, 0., 0.));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                    });
-            }
-            PieceType::Queen => {
-                commands
-                    .spawn_bundle(PbrBundle {
-                        transform: Transform::from_translation(Vec3::new(piece.x, 0., piece.y)),
-                        ..Default::default()
-                    })
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PbrBundle {
-                            mesh: meshes[0].clone(),
-                            material: material.clone(),
-                            transform: {
-                                let mut transform =
-                                    Transform::from_translation(Vec3::new(-0.2, 0., -0.95));
-                                transform.apply_non_uniform_scale(Vec3::new(0.2, 0.2, 0.2));
-                                transform
-                            },
-                            ..Default::default()
-                        });
-                    });
-            }
+    pub fn spawn(commands: &mut Commands, piece: Piece, assets: &PieceAssets) -> Entity {
+        commands
+            .spawn_bundle(PbrBundle {
+                transform: Transform::from_translation(square_translation(piece.square)),
+                ..Default::default()
+            })
+            .insert(piece)
+            .with_children(|parent| Piece::spawn_parts(parent, piece, assets))
+            .id()
+    }
+
+    /// Spawns the meshes of `piece` as children of its entity. There are
+    /// none before the piece set has loaded, `piece_set` adds them then.
+    pub fn spawn_parts(parent: &mut ChildBuilder, piece: Piece, assets: &PieceAssets) {
+        let parts = match assets.models.get(&assets.model(piece.ptype)) {
+            Some(parts) => parts,
+            None => return,
         };
+        let material = assets.material(piece.ptype, piece.color);
+        for mesh in &parts.meshes {
+            parent.spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: parts.transform,
+                ..Default::default()
+            });
+        }
     }
 }
@@ -0,0 +1,149 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use std::collections::HashMap;
+
+use crate::board::BoardSystem;
+use crate::piece::*;
+
+pub fn init(app: &mut App) {
+    app.add_asset::<PieceSet>()
+        .init_asset_loader::<PieceSetLoader>()
+        .add_system(apply_piece_set.before(BoardSystem::SyncPieces));
+}
+
+/// Meshes and placement of one piece in a piece set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PieceModel {
+    /// Asset paths, e.g. `model/chess_kit/pieces.glb#Mesh0/Primitive0`.
+    pub meshes: Vec<String>,
+    /// From the centre of the square.
+    pub offset: [f32; 3],
+    /// Degrees about the vertical axis.
+    #[serde(default)]
+    pub rotation: f32,
+    pub scale: f32,
+}
+
+impl PieceModel {
+    pub fn transform(&self) -> Transform {
+        Transform {
+            translation: Vec3::from(self.offset),
+            rotation: Quat::from_rotation_y(self.rotation.to_radians()),
+            scale: Vec3::splat(self.scale),
+        }
+    }
+}
+
+/// How the standard pieces look, loaded from a `.pieces.toml` file with a
+/// table for each of them.
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "6f2b9d4e-8a13-4c57-b0e2-71d5c3a8f946"]
+pub struct PieceSet {
+    pub king: PieceModel,
+    pub pawn: PieceModel,
+    pub knight: PieceModel,
+    pub rook: PieceModel,
+    pub bishop: PieceModel,
+    pub queen: PieceModel,
+}
+
+impl PieceSet {
+    /// The model of a standard piece, fairy pieces get the pawn's.
+    pub fn model(&self, ptype: PieceType) -> &PieceModel {
+        match ptype {
+            PieceType::King => &self.king,
+            PieceType::Pawn | PieceType::Fairy(_) => &self.pawn,
+            PieceType::Knight => &self.knight,
+            PieceType::Rook => &self.rook,
+            PieceType::Bishop => &self.bishop,
+            PieceType::Queen => &self.queen,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PieceSetLoader;
+
+impl AssetLoader for PieceSetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let set: PieceSet = toml::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(set));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pieces.toml"]
+    }
+}
+
+/// Takes the meshes of `PieceAssets::set` once it has loaded, again
+/// whenever its file changes and when another set is chosen, and rebuilds
+/// every piece on the board with them. Runs before `sync_pieces` so that
+/// pieces it spawns get them too.
+fn apply_piece_set(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<PieceSet>>,
+    sets: Res<Assets<PieceSet>>,
+    asset_server: Res<AssetServer>,
+    mut assets: ResMut<PieceAssets>,
+    pieces: Query<(Entity, &Piece, Option<&Children>)>,
+    mut applied: Local<Option<Handle<PieceSet>>>,
+) {
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => *handle == assets.set,
+        _ => false,
+    });
+    if !modified && applied.as_ref() == Some(&assets.set) {
+        return;
+    }
+    let set = match sets.get(&assets.set) {
+        Some(set) => set,
+        None => return,
+    };
+    *applied = Some(assets.set.clone());
+
+    let mut models = HashMap::new();
+    for ptype in [
+        PieceType::King,
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Queen,
+    ] {
+        let model = set.model(ptype);
+        let parts = PieceParts {
+            meshes: model
+                .meshes
+                .iter()
+                .map(|path| asset_server.load(path.as_str()))
+                .collect(),
+            transform: model.transform(),
+        };
+        models.insert(ptype, parts);
+    }
+    assets.models = models;
+
+    for (entity, &piece, children) in pieces.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+        commands
+            .entity(entity)
+            .with_children(|parent| Piece::spawn_parts(parent, piece, &assets));
+    }
+}
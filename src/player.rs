@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+
+use crate::rules::PieceColor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerKind {
+    Human,
+    /// The built-in engine in `ai`.
+    Computer,
+    /// An external UCI engine, see `uci_engine`.
+    External,
+    /// The player at the other end of a network game, see `network`.
+    Remote,
+}
+
+/// Who makes the moves for each side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Players {
+    pub white: PlayerKind,
+    pub black: PlayerKind,
+}
+
+impl Default for Players {
+    fn default() -> Self {
+        Players {
+            white: PlayerKind::Human,
+            black: PlayerKind::Human,
+        }
+    }
+}
+
+impl Players {
+    pub fn get(&self, color: PieceColor) -> PlayerKind {
+        match color {
+            PieceColor::White => self.white,
+            PieceColor::Black => self.black,
+        }
+    }
+
+    pub fn set(&mut self, color: PieceColor, kind: PlayerKind) {
+        match color {
+            PieceColor::White => self.white = kind,
+            PieceColor::Black => self.black = kind,
+        }
+    }
+}
+
+/// Parses a command line side selection: `white`, `black` or `both`.
+pub fn parse_sides(sides: &str) -> Option<&'static [PieceColor]> {
+    match sides {
+        "white" => Some(&[PieceColor::White]),
+        "black" => Some(&[PieceColor::Black]),
+        "both" => Some(&[PieceColor::White, PieceColor::Black]),
+        _ => None,
+    }
+}
+
+pub fn init(app: &mut App) {
+    app.init_resource::<Players>();
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::board::MakeMove;
+use crate::rules::{Board, Move, PieceType, Square, PROMOTION_PIECES};
+
+pub fn init(app: &mut App) {
+    // The egui plugin itself is added by the editor plugin
+    app.init_resource::<PendingPromotion>()
+        .add_system(promotion_popup);
+}
+
+/// A pawn move to the last rank waiting for the player to pick a piece.
+#[derive(Default)]
+pub struct PendingPromotion(pub Option<(Square, Square)>);
+
+fn promotion_popup(
+    mut egui_context: ResMut<EguiContext>,
+    board: Res<Board>,
+    mut pending: ResMut<PendingPromotion>,
+    mut make_move: EventWriter<MakeMove>,
+) {
+    let (from, to) = match pending.0 {
+        Some(squares) => squares,
+        None => return,
+    };
+    // The position moved on, e.g. the game ended on time
+    let choices: Vec<Move> = board
+        .legal_moves_from(from)
+        .into_iter()
+        .filter(|mv| mv.to == to && mv.promotion().is_some())
+        .collect();
+    if choices.is_empty() {
+        pending.0 = None;
+        return;
+    }
+
+    let mut chosen = None;
+    let mut cancelled = false;
+    egui::Window::new("Promote to")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for &ptype in PROMOTION_PIECES.iter() {
+                    let label = match ptype {
+                        PieceType::Queen => "Queen",
+                        PieceType::Rook => "Rook",
+                        PieceType::Bishop => "Bishop",
+                        _ => "Knight",
+                    };
+                    if ui.button(label).clicked() {
+                        chosen = choices
+                            .iter()
+                            .find(|mv| mv.promotion() == Some(ptype))
+                            .copied();
+                    }
+                }
+            });
+            cancelled = ui.button("Cancel").clicked();
+        });
+
+    if let Some(mv) = chosen {
+        make_move.send(MakeMove(mv));
+        pending.0 = None;
+    } else if cancelled {
+        pending.0 = None;
+    }
+}
@@ -0,0 +1,483 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+    /// Files of the castling rooks in the order of the flags above, the h
+    /// and a files in classical chess but anywhere in Chess960.
+    rook_files: [u8; 4],
+}
+
+impl Default for CastlingRights {
+    fn default() -> Self {
+        CastlingRights {
+            white_king_side: false,
+            white_queen_side: false,
+            black_king_side: false,
+            black_queen_side: false,
+            rook_files: [7, 0, 7, 0],
+        }
+    }
+}
+
+impl CastlingRights {
+    pub const ALL: CastlingRights = CastlingRights {
+        white_king_side: true,
+        white_queen_side: true,
+        black_king_side: true,
+        black_queen_side: true,
+        rook_files: [7, 0, 7, 0],
+    };
+
+    fn index(color: PieceColor, king_side: bool) -> usize {
+        match (color, king_side) {
+            (PieceColor::White, true) => 0,
+            (PieceColor::White, false) => 1,
+            (PieceColor::Black, true) => 2,
+            (PieceColor::Black, false) => 3,
+        }
+    }
+
+    /// File of the rook `color` castles with on that side.
+    pub fn rook_file(&self, color: PieceColor, king_side: bool) -> u8 {
+        self.rook_files[Self::index(color, king_side)]
+    }
+
+    pub fn set_rook_file(&mut self, color: PieceColor, king_side: bool, file: u8) {
+        self.rook_files[Self::index(color, king_side)] = file;
+    }
+
+    pub fn get(&self, color: PieceColor, king_side: bool) -> bool {
+        match (color, king_side) {
+            (PieceColor::White, true) => self.white_king_side,
+            (PieceColor::White, false) => self.white_queen_side,
+            (PieceColor::Black, true) => self.black_king_side,
+            (PieceColor::Black, false) => self.black_queen_side,
+        }
+    }
+
+    pub fn set(&mut self, color: PieceColor, king_side: bool, value: bool) {
+        match (color, king_side) {
+            (PieceColor::White, true) => self.white_king_side = value,
+            (PieceColor::White, false) => self.white_queen_side = value,
+            (PieceColor::Black, true) => self.black_king_side = value,
+            (PieceColor::Black, false) => self.black_queen_side = value,
+        }
+    }
+}
+
+#[rustfmt::skip]
+const BACK_RANK: [PieceType; 8] = [
+    PieceType::Rook, PieceType::Knight, PieceType::Bishop, PieceType::Queen,
+    PieceType::King, PieceType::Bishop, PieceType::Knight, PieceType::Rook,
+];
+
+/// Logical chess position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Board {
+    /// Indexed as `squares[rank][file]`, only the first `size` of each are
+    /// used.
+    squares: [[Option<(PieceType, PieceColor)>; MAX_FILES as usize]; MAX_RANKS as usize],
+    size: BoardSize,
+    pub side_to_move: PieceColor,
+    pub castling: CastlingRights,
+    /// Square a pawn skipped over with a double push on the previous move.
+    pub en_passant: Option<Square>,
+    /// Plies since the last capture or pawn move, for the fifty-move rule.
+    pub halfmove_clock: u32,
+    /// Starts at 1 and is incremented after each Black move.
+    pub fullmove_number: u32,
+    /// Castling rooks may start anywhere, which UCI writes as the king
+    /// taking its own rook.
+    pub chess960: bool,
+    /// The rules this position is played under.
+    pub variant: VariantKind,
+    /// What the variant keeps track of beyond the pieces on the board.
+    pub variant_state: VariantState,
+    /// The fairy pieces defined when the board was made.
+    fairy: FairyPieces,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::start()
+    }
+}
+
+impl Board {
+    /// An empty 8x8 board.
+    pub fn empty() -> Self {
+        Board::empty_with_size(BoardSize::STANDARD)
+    }
+
+    pub fn empty_with_size(size: BoardSize) -> Self {
+        Board::empty_with_fairy_pieces(size, FairyPieces::current())
+    }
+
+    /// An empty board whose fairy pieces are `fairy`, for positions read
+    /// with those definitions.
+    pub(super) fn empty_with_fairy_pieces(size: BoardSize, fairy: FairyPieces) -> Self {
+        Board {
+            squares: [[None; MAX_FILES as usize]; MAX_RANKS as usize],
+            size,
+            side_to_move: PieceColor::White,
+            castling: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            chess960: false,
+            variant: VariantKind::Standard,
+            variant_state: VariantState::default(),
+            fairy,
+        }
+    }
+
+    /// The classical starting position.
+    pub fn start() -> Self {
+        Board::with_back_rank(&BACK_RANK, 8)
+    }
+
+    /// Starting position on a board as wide as `back_rank` with `ranks`
+    /// ranks, the pieces behind the pawns in `back_rank`, mirrored for
+    /// Black. Castles with the outermost rooks on boards at least 8 wide.
+    pub fn with_back_rank(back_rank: &[PieceType], ranks: u8) -> Self {
+        let size = BoardSize::new(back_rank.len() as u8, ranks).expect("board too large");
+        let mut board = Board::empty_with_size(size);
+        for (file, &ptype) in back_rank.iter().enumerate() {
+            let file = file as u8;
+            board.set(Square::new(file, 0), Some((ptype, PieceColor::White)));
+            board.set(
+                Square::new(file, 1),
+                Some((PieceType::Pawn, PieceColor::White)),
+            );
+            board.set(
+                Square::new(file, ranks - 2),
+                Some((PieceType::Pawn, PieceColor::Black)),
+            );
+            board.set(
+                Square::new(file, ranks - 1),
+                Some((ptype, PieceColor::Black)),
+            );
+        }
+        if size.files < 8 {
+            return board;
+        }
+        board.castling = CastlingRights::ALL;
+        for king_side in [true, false] {
+            if let Some(file) = board.outermost_rook(PieceColor::White, king_side) {
+                board
+                    .castling
+                    .set_rook_file(PieceColor::White, king_side, file);
+                board
+                    .castling
+                    .set_rook_file(PieceColor::Black, king_side, file);
+            }
+        }
+        board
+    }
+
+    /// File of the rook furthest from the king on its back rank, on the
+    /// king or queen side.
+    pub fn outermost_rook(&self, color: PieceColor, king_side: bool) -> Option<u8> {
+        let rank = self.back_rank(color);
+        let king = self.king_square(color).filter(|king| king.rank == rank)?;
+        let rook = |&file: &u8| self.get(Square::new(file, rank)) == Some((PieceType::Rook, color));
+        if king_side {
+            (king.file + 1..self.size.files).rev().find(rook)
+        } else {
+            (0..king.file).find(rook)
+        }
+    }
+
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
+    /// Definition of a fairy piece on this board, `None` for the standard
+    /// pieces.
+    pub fn fairy(&self, ptype: PieceType) -> Option<&FairyPiece> {
+        self.fairy.get(ptype)
+    }
+
+    pub fn fairy_pieces(&self) -> &FairyPieces {
+        &self.fairy
+    }
+
+    /// Rank the pieces of `color` start on.
+    pub fn back_rank(&self, color: PieceColor) -> u8 {
+        match color {
+            PieceColor::Black => self.size.ranks - 1,
+            PieceColor::White => 0,
+        }
+    }
+
+    /// Returns the square `df` files and `dr` ranks away from `square`, if
+    /// it is on this board.
+    pub fn offset(&self, square: Square, df: i8, dr: i8) -> Option<Square> {
+        square
+            .offset(df, dr)
+            .filter(|&square| self.size.contains(square))
+    }
+
+    pub fn get(&self, square: Square) -> Option<(PieceType, PieceColor)> {
+        self.squares[square.rank as usize][square.file as usize]
+    }
+
+    pub fn set(&mut self, square: Square, content: Option<(PieceType, PieceColor)>) {
+        self.squares[square.rank as usize][square.file as usize] = content;
+    }
+
+    /// Iterates over every occupied square.
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, PieceType, PieceColor)> + '_ {
+        self.size.squares().filter_map(move |square| {
+            self.get(square)
+                .map(|(ptype, color)| (square, ptype, color))
+        })
+    }
+
+    pub fn king_square(&self, color: PieceColor) -> Option<Square> {
+        self.pieces()
+            .find(|&(_, ptype, c)| ptype == PieceType::King && c == color)
+            .map(|(square, _, _)| square)
+    }
+
+    /// Plays `mv` without checking that it is legal, including whatever the
+    /// variant adds to it, like explosions in Atomic.
+    pub fn make_move(&mut self, mv: Move) {
+        let (ptype, color) = match (mv.dropped(), self.get(mv.from)) {
+            (Some(ptype), _) => (ptype, self.side_to_move),
+            (None, Some(piece)) => piece,
+            (None, None) => return,
+        };
+        let captured = match mv.kind {
+            MoveKind::EnPassant => self.get(Square::new(mv.to.file, mv.from.rank)),
+            _ => self.get(mv.to).filter(|_| mv.is_capture()),
+        };
+
+        if ptype == PieceType::Pawn || mv.is_capture() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.set(mv.from, None);
+        match mv.kind {
+            MoveKind::EnPassant => {
+                self.set(Square::new(mv.to.file, mv.from.rank), None);
+                self.set(mv.to, Some((ptype, color)));
+            }
+            MoveKind::KingsideCastle | MoveKind::QueensideCastle => {
+                let (rook_from, rook_to) = self.castling_rook_squares(mv);
+                self.set(rook_from, None);
+                self.set(rook_to, Some((PieceType::Rook, color)));
+                self.set(mv.to, Some((ptype, color)));
+            }
+            MoveKind::Promotion(promoted) | MoveKind::PromotionCapture(promoted) => {
+                self.set(mv.to, Some((promoted, color)));
+            }
+            _ => {
+                self.set(mv.to, Some((ptype, color)));
+            }
+        }
+
+        if ptype == PieceType::King {
+            self.castling.set(color, true, false);
+            self.castling.set(color, false, false);
+        }
+        for square in [mv.from, mv.to] {
+            for color in [PieceColor::White, PieceColor::Black] {
+                for king_side in [true, false] {
+                    let rook_file = self.castling.rook_file(color, king_side);
+                    if square == Square::new(rook_file, self.back_rank(color)) {
+                        self.castling.set(color, king_side, false);
+                    }
+                }
+            }
+        }
+
+        self.en_passant = match mv.kind {
+            MoveKind::DoublePawnPush => mv.from.offset(0, color.forward()),
+            _ => None,
+        };
+        self.side_to_move = color.opponent();
+        self.variant.rules().after_move(self, mv, captured);
+    }
+
+    /// Plays `mv` like `make_move` and returns what `unmake_move` needs to
+    /// take it back.
+    pub fn make_reversible_move(&mut self, mv: Move) -> Undo {
+        let before = self.clone();
+        self.make_move(mv);
+        Undo {
+            squares: self
+                .size
+                .squares()
+                .filter(|&square| self.get(square) != before.get(square))
+                .map(|square| (square, before.get(square)))
+                .collect(),
+            castling: before.castling,
+            en_passant: before.en_passant,
+            halfmove_clock: before.halfmove_clock,
+            fullmove_number: before.fullmove_number,
+            variant_state: before.variant_state,
+        }
+    }
+
+    /// Takes back the last move made with `make_reversible_move`.
+    pub fn unmake_move(&mut self, undo: &Undo) {
+        for &(square, content) in &undo.squares {
+            self.set(square, content);
+        }
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.variant_state = undo.variant_state;
+        self.side_to_move = self.side_to_move.opponent();
+    }
+}
+
+/// The state a move destroys, kept to take the move back. Squares are kept
+/// as they were, which covers every variant, explosions and drops included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Undo {
+    pub squares: Vec<(Square, Option<(PieceType, PieceColor)>)>,
+    pub castling: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    pub variant_state: VariantState,
+}
+
+/// What a move does to a single piece on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceChange {
+    Moved {
+        from: Square,
+        to: Square,
+    },
+    Removed(Square),
+    /// The piece on the square becomes another type, i.e. a promotion.
+    Replaced {
+        square: Square,
+        ptype: PieceType,
+    },
+}
+
+impl Board {
+    /// The pieces touched by `mv`, which is about to be played. They are
+    /// meant to be carried out as captures first so the destination square
+    /// is free, then the moving pieces all at once since the king and rook
+    /// may swap squares in Chess960, then a promotion. Dropped pieces are not
+    /// part of them.
+    pub fn piece_changes(&self, mv: Move) -> Vec<PieceChange> {
+        let mut after = self.clone();
+        after.make_move(mv);
+        let mut changes = Vec::new();
+        match mv.kind {
+            MoveKind::Capture | MoveKind::PromotionCapture(_) => {
+                changes.push(PieceChange::Removed(mv.to));
+            }
+            MoveKind::EnPassant => {
+                changes.push(PieceChange::Removed(Square::new(mv.to.file, mv.from.rank)));
+            }
+            _ => {}
+        }
+        if mv.from != mv.to {
+            changes.push(PieceChange::Moved {
+                from: mv.from,
+                to: mv.to,
+            });
+        }
+        if mv.is_castle() {
+            let (from, to) = self.castling_rook_squares(mv);
+            if from != to {
+                changes.push(PieceChange::Moved { from, to });
+            }
+        }
+        if let Some(ptype) = mv.promotion() {
+            changes.push(PieceChange::Replaced {
+                square: mv.to,
+                ptype,
+            });
+        }
+
+        // Pieces the variant takes off as well, like an exploding capturer
+        for change in &mut changes {
+            if let PieceChange::Moved { from, to } = *change {
+                if after.get(to).is_none() {
+                    *change = PieceChange::Removed(from);
+                }
+            }
+        }
+        for square in self.size.squares() {
+            let accounted = changes.iter().any(|&change| match change {
+                PieceChange::Removed(removed) => removed == square,
+                PieceChange::Moved { from, .. } => from == square,
+                PieceChange::Replaced { .. } => false,
+            });
+            if !accounted && self.get(square).is_some() && after.get(square).is_none() {
+                changes.push(PieceChange::Removed(square));
+            }
+        }
+        changes.retain(|&change| match change {
+            PieceChange::Replaced { square, .. } => after.get(square).is_some(),
+            _ => true,
+        });
+        changes
+    }
+
+    /// The pieces touched when `mv` is taken back, in the same order as
+    /// `piece_changes`. A captured piece comes back on its own and is not
+    /// part of them.
+    pub fn unmake_piece_changes(&self, mv: Move) -> Vec<PieceChange> {
+        let mut changes = Vec::new();
+        if mv.from != mv.to {
+            changes.push(PieceChange::Moved {
+                from: mv.to,
+                to: mv.from,
+            });
+        }
+        if mv.is_castle() {
+            let (from, to) = self.castling_rook_squares(mv);
+            if from != to {
+                changes.push(PieceChange::Moved { from: to, to: from });
+            }
+        }
+        if mv.promotion().is_some() {
+            changes.push(PieceChange::Replaced {
+                square: mv.from,
+                ptype: PieceType::Pawn,
+            });
+        }
+        changes
+    }
+
+    /// Where the rook starts and ends for a castling move. The king always
+    /// ends on the c file or the second file from the right, g on 8 files,
+    /// and the rook next to it on the inside.
+    pub fn castling_rook_squares(&self, mv: Move) -> (Square, Square) {
+        let rank = mv.from.rank;
+        let color = if rank == self.back_rank(PieceColor::White) {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        match mv.kind {
+            MoveKind::QueensideCastle => (
+                Square::new(self.castling.rook_file(color, false), rank),
+                Square::new(3, rank),
+            ),
+            _ => (
+                Square::new(self.castling.rook_file(color, true), rank),
+                Square::new(self.size.files - 3, rank),
+            ),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+
+/// Number of Chess960 starting positions.
+pub const CHESS960_POSITIONS: u16 = 960;
+
+/// Index of the classical setup in the standard numbering.
+pub const CLASSICAL_INDEX: u16 = 518;
+
+/// Where the two knights go among the five files left after the bishops and
+/// the queen are placed.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The back rank of Chess960 position `index`, numbered as by Scharnagl so
+/// that 518 is the classical setup. Bishops go on opposite colors and the
+/// king between the rooks.
+pub fn chess960_back_rank(index: u16) -> Option<[PieceType; 8]> {
+    if index >= CHESS960_POSITIONS {
+        return None;
+    }
+    let mut rank: [Option<PieceType>; 8] = [None; 8];
+    let mut n = index as usize;
+
+    rank[2 * (n % 4) + 1] = Some(PieceType::Bishop);
+    n /= 4;
+    rank[2 * (n % 4)] = Some(PieceType::Bishop);
+    n /= 4;
+
+    let empty = |rank: &[Option<PieceType>; 8]| -> Vec<usize> {
+        (0..8).filter(|&file| rank[file].is_none()).collect()
+    };
+    rank[empty(&rank)[n % 6]] = Some(PieceType::Queen);
+    n /= 6;
+
+    let files = empty(&rank);
+    let (first, second) = KNIGHT_PLACEMENTS[n];
+    rank[files[first]] = Some(PieceType::Knight);
+    rank[files[second]] = Some(PieceType::Knight);
+
+    let files = empty(&rank);
+    rank[files[0]] = Some(PieceType::Rook);
+    rank[files[1]] = Some(PieceType::King);
+    rank[files[2]] = Some(PieceType::Rook);
+
+    let mut pieces = [PieceType::Pawn; 8];
+    for (piece, placed) in pieces.iter_mut().zip(rank) {
+        *piece = placed?;
+    }
+    Some(pieces)
+}
+
+/// A Chess960 index picked from `seed`, so the same seed gives the same
+/// position.
+pub fn chess960_index_from_seed(seed: u64) -> u16 {
+    // splitmix64 finalizer
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    ((z ^ (z >> 31)) % CHESS960_POSITIONS as u64) as u16
+}
+
+impl Board {
+    /// Starting position `index` of Chess960, in `0..960`.
+    pub fn chess960(index: u16) -> Option<Board> {
+        let mut board = Board::with_back_rank(&chess960_back_rank(index)?, 8);
+        board.chess960 = true;
+        Some(board)
+    }
+}
@@ -0,0 +1,288 @@
+use super::*;
+
+use std::{error::Error, fmt, str::FromStr, time::Duration};
+
+/// Time added for each move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bonus {
+    None,
+    /// Fischer increment, added after every move.
+    Increment(Duration),
+    /// Bronstein delay, the time used for a move is given back up to this
+    /// amount.
+    Delay(Duration),
+}
+
+/// One stage of a time control. The last period repeats for the rest of
+/// the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    /// Moves to make within the period, `None` for the rest of the game.
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Bonus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    pub periods: Vec<Period>,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: Duration) -> Self {
+        TimeControl {
+            periods: vec![Period {
+                moves: None,
+                time,
+                bonus: Bonus::None,
+            }],
+        }
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        TimeControl {
+            periods: vec![Period {
+                moves: None,
+                time,
+                bonus: Bonus::Increment(increment),
+            }],
+        }
+    }
+
+    pub fn bronstein(time: Duration, delay: Duration) -> Self {
+        TimeControl {
+            periods: vec![Period {
+                moves: None,
+                time,
+                bonus: Bonus::Delay(delay),
+            }],
+        }
+    }
+
+    /// The increment of a control with one period for the whole game, zero
+    /// without a bonus. `None` for delays and controls with several
+    /// periods, which UCI's `winc` and `binc` can't describe.
+    pub fn fischer_increment(&self) -> Option<Duration> {
+        match self.periods.as_slice() {
+            [period] if period.moves.is_none() => match period.bonus {
+                Bonus::None => Some(Duration::ZERO),
+                Bonus::Increment(increment) => Some(increment),
+                Bonus::Delay(_) => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControlError(pub String);
+
+impl fmt::Display for TimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid time control '{}', expected periods like '300', '300+2', '300d2' or '40/7200:3600'",
+            self.0
+        )
+    }
+}
+
+impl Error for TimeControlError {}
+
+/// The PGN `TimeControl` tag syntax: periods separated by `:`, each being
+/// `[moves/]seconds` optionally followed by `+increment`. A Bronstein delay
+/// is written `dDelay`, which is an extension of the standard.
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || TimeControlError(s.to_string());
+        let seconds = |s: &str| {
+            s.parse::<u64>()
+                .map(Duration::from_secs)
+                .map_err(|_| error())
+        };
+
+        let mut periods = Vec::new();
+        for period in s.split(':') {
+            let (moves, rest) = match period.split_once('/') {
+                Some((moves, rest)) => (Some(moves.parse().map_err(|_| error())?), rest),
+                None => (None, period),
+            };
+            let (time, bonus) = if let Some((time, increment)) = rest.split_once('+') {
+                (time, Bonus::Increment(seconds(increment)?))
+            } else if let Some((time, delay)) = rest.split_once('d') {
+                (time, Bonus::Delay(seconds(delay)?))
+            } else {
+                (rest, Bonus::None)
+            };
+            periods.push(Period {
+                moves,
+                time: seconds(time)?,
+                bonus,
+            });
+        }
+        Ok(TimeControl { periods })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, period) in self.periods.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            if let Some(moves) = period.moves {
+                write!(f, "{}/", moves)?;
+            }
+            write!(f, "{}", period.time.as_secs())?;
+            match period.bonus {
+                Bonus::None => {}
+                Bonus::Increment(increment) => write!(f, "+{}", increment.as_secs())?,
+                Bonus::Delay(delay) => write!(f, "d{}", delay.as_secs())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Side {
+    remaining: Duration,
+    period: usize,
+    moves_in_period: u32,
+}
+
+/// A two-sided chess clock. The caller advances the clock of the side to
+/// move with `tick` and calls `press` once that side has moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChessClock {
+    pub control: TimeControl,
+    white: Side,
+    black: Side,
+    /// Time spent on the current move, for the Bronstein delay.
+    current_move: Duration,
+    flagged: Option<PieceColor>,
+}
+
+impl ChessClock {
+    pub fn new(control: TimeControl) -> Self {
+        let side = Side {
+            remaining: control.periods.first().map_or(Duration::ZERO, |p| p.time),
+            period: 0,
+            moves_in_period: 0,
+        };
+        ChessClock {
+            control,
+            white: side,
+            black: side,
+            current_move: Duration::ZERO,
+            flagged: None,
+        }
+    }
+
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        self.side(color).remaining
+    }
+
+    /// Overrides the time left, e.g. when restoring a saved game.
+    pub fn set_remaining(&mut self, color: PieceColor, remaining: Duration) {
+        self.side_mut(color).remaining = remaining;
+    }
+
+    /// Rewinds the clock to the end of `record`: periods and bonuses as if
+    /// its moves were played, time left from their `%clk` values.
+    pub fn restore(&mut self, record: &GameRecord) {
+        *self = ChessClock::new(self.control.clone());
+        let mut color = record.start.side_to_move;
+        for recorded in &record.moves {
+            self.press(color);
+            if let Some(clock) = recorded.clock {
+                self.set_remaining(color, clock);
+            }
+            color = color.opponent();
+        }
+    }
+
+    /// The side whose time ran out, if any.
+    pub fn flagged(&self) -> Option<PieceColor> {
+        self.flagged
+    }
+
+    /// Runs the clock of `color` for `elapsed`. Returns true if that made
+    /// its flag fall.
+    pub fn tick(&mut self, color: PieceColor, elapsed: Duration) -> bool {
+        if self.flagged.is_some() {
+            return false;
+        }
+        self.current_move = self.current_move.saturating_add(elapsed);
+        let side = self.side_mut(color);
+        side.remaining = side.remaining.saturating_sub(elapsed);
+        if side.remaining == Duration::ZERO {
+            self.flagged = Some(color);
+            return true;
+        }
+        false
+    }
+
+    /// Ends the move of `color`: applies the bonus of its current period and
+    /// moves on to the next period once enough moves were made.
+    pub fn press(&mut self, color: PieceColor) {
+        let used = std::mem::replace(&mut self.current_move, Duration::ZERO);
+        if self.flagged.is_some() {
+            return;
+        }
+        let periods = &self.control.periods;
+        let side = match color {
+            PieceColor::White => &mut self.white,
+            PieceColor::Black => &mut self.black,
+        };
+        let period = match periods.get(side.period) {
+            Some(period) => *period,
+            None => return,
+        };
+
+        match period.bonus {
+            Bonus::None => {}
+            Bonus::Increment(increment) => {
+                side.remaining = side.remaining.saturating_add(increment);
+            }
+            Bonus::Delay(delay) => side.remaining = side.remaining.saturating_add(used.min(delay)),
+        }
+
+        side.moves_in_period += 1;
+        if Some(side.moves_in_period) == period.moves {
+            side.moves_in_period = 0;
+            side.period = (side.period + 1).min(periods.len() - 1);
+            side.remaining = side.remaining.saturating_add(periods[side.period].time);
+        }
+    }
+
+    fn side(&self, color: PieceColor) -> &Side {
+        match color {
+            PieceColor::White => &self.white,
+            PieceColor::Black => &self.black,
+        }
+    }
+
+    fn side_mut(&mut self, color: PieceColor) -> &mut Side {
+        match color {
+            PieceColor::White => &mut self.white,
+            PieceColor::Black => &mut self.black,
+        }
+    }
+}
+
+/// `h:mm:ss` as used by the PGN `%clk` command.
+pub fn format_clock(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+pub fn parse_clock(s: &str) -> Option<Duration> {
+    let mut secs = 0.0;
+    for part in s.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
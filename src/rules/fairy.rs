@@ -0,0 +1,371 @@
+use super::*;
+
+use serde::Deserialize;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+use std::{error::Error, fmt};
+
+/// Most fairy pieces that can be defined at the same time.
+pub const MAX_FAIRY_PIECES: usize = 16;
+
+/// Whether a fairy piece may move, capture or both in a direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveMode {
+    MoveOrCapture,
+    MoveOnly,
+    CaptureOnly,
+}
+
+impl MoveMode {
+    pub fn moves(self) -> bool {
+        self != MoveMode::CaptureOnly
+    }
+
+    pub fn captures(self) -> bool {
+        self != MoveMode::MoveOnly
+    }
+}
+
+/// One direction a fairy piece moves in, seen from White. Black's moves are
+/// turned around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FairyMove {
+    /// Files and ranks covered by a single step.
+    pub step: (i8, i8),
+    /// Most steps taken in a line, `None` for as far as the board goes.
+    pub range: Option<u8>,
+    pub mode: MoveMode,
+    /// Jumps the first piece in the line and lands right behind it, like
+    /// the grasshopper.
+    pub hop: bool,
+}
+
+impl FairyMove {
+    /// The step for a piece of `color`.
+    pub fn step_for(self, color: PieceColor) -> (i8, i8) {
+        match color {
+            PieceColor::White => self.step,
+            PieceColor::Black => (-self.step.0, -self.step.1),
+        }
+    }
+}
+
+/// A piece defined in a pieces file rather than in the rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairyPiece {
+    pub name: String,
+    /// Lowercase letter used in FEN, uppercase for White and in SAN.
+    pub letter: char,
+    /// Movement in Betza notation, e.g. `BN` for the archbishop.
+    pub betza: String,
+    /// Worth in centipawns, for the engine.
+    pub value: i32,
+    pub moves: Vec<FairyMove>,
+}
+
+impl FairyPiece {
+    pub fn new(name: &str, letter: char, betza: &str, value: i32) -> Result<Self, FairyError> {
+        let letter = letter.to_ascii_lowercase();
+        if !letter.is_ascii_lowercase() || "kpnrbq".contains(letter) {
+            return Err(FairyError::Letter(name.to_string(), letter));
+        }
+        let moves = parse_betza(betza)
+            .ok_or_else(|| FairyError::Betza(name.to_string(), betza.to_string()))?;
+        Ok(FairyPiece {
+            name: name.to_string(),
+            letter,
+            betza: betza.to_string(),
+            value,
+            moves,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FairyError {
+    /// The pieces file is not valid TOML or misses a field.
+    Parse(String),
+    /// A piece name and its movement that could not be read.
+    Betza(String, String),
+    /// A piece name and a letter that is not free for it.
+    Letter(String, char),
+    TooMany,
+}
+
+impl fmt::Display for FairyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FairyError::Parse(err) => write!(f, "{}", err),
+            FairyError::Betza(name, betza) => {
+                write!(f, "{}: invalid Betza notation '{}'", name, betza)
+            }
+            FairyError::Letter(name, letter) => {
+                write!(f, "{}: letter '{}' is already taken", name, letter)
+            }
+            FairyError::TooMany => {
+                write!(
+                    f,
+                    "no more than {} fairy pieces can be defined",
+                    MAX_FAIRY_PIECES
+                )
+            }
+        }
+    }
+}
+
+impl Error for FairyError {}
+
+/// A set of fairy piece definitions, `PieceType::Fairy` indexes into it.
+/// Shared rather than copied, so boards can keep the one they were made
+/// with and look pieces up without locking.
+#[derive(Debug, Clone, Default)]
+pub struct FairyPieces(Option<Arc<[FairyPiece]>>);
+
+impl FairyPieces {
+    /// The pieces defined right now.
+    pub fn current() -> FairyPieces {
+        FAIRY_PIECES.read().unwrap().clone()
+    }
+
+    pub fn get(&self, ptype: PieceType) -> Option<&FairyPiece> {
+        match ptype {
+            PieceType::Fairy(id) => self.as_slice().get(id as usize),
+            _ => None,
+        }
+    }
+
+    fn as_slice(&self) -> &[FairyPiece] {
+        self.0.as_deref().unwrap_or_default()
+    }
+
+    /// Every piece with its `PieceType`.
+    pub fn iter(&self) -> impl Iterator<Item = (PieceType, &FairyPiece)> {
+        self.as_slice()
+            .iter()
+            .enumerate()
+            .map(|(id, piece)| (PieceType::Fairy(id as u8), piece))
+    }
+
+    /// The piece written as `letter` in either case.
+    pub fn by_letter(&self, letter: char) -> Option<PieceType> {
+        self.position(letter.to_ascii_lowercase())
+            .map(|id| PieceType::Fairy(id as u8))
+    }
+
+    fn position(&self, letter: char) -> Option<usize> {
+        self.as_slice()
+            .iter()
+            .position(|piece| piece.letter == letter)
+    }
+}
+
+impl PartialEq for FairyPieces {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) if Arc::ptr_eq(a, b) => true,
+            _ => self.as_slice() == other.as_slice(),
+        }
+    }
+}
+
+impl Eq for FairyPieces {}
+
+/// Left out of board hashes, like the rest of the rules.
+impl Hash for FairyPieces {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Every defined fairy piece. Definitions are replaced, never changed in
+/// place, so the sets boards hold stay as they were.
+static FAIRY_PIECES: RwLock<FairyPieces> = RwLock::new(FairyPieces(None));
+
+/// The fairy piece written as `letter` in either case.
+pub fn fairy_piece(letter: char) -> Option<PieceType> {
+    FairyPieces::current().by_letter(letter)
+}
+
+/// Adds `piece` to `pieces`, replacing an earlier one with the same letter.
+fn add_fairy_piece(
+    pieces: &mut Vec<FairyPiece>,
+    piece: FairyPiece,
+) -> Result<PieceType, FairyError> {
+    match pieces.iter().position(|p| p.letter == piece.letter) {
+        Some(id) => {
+            pieces[id] = piece;
+            Ok(PieceType::Fairy(id as u8))
+        }
+        None if pieces.len() < MAX_FAIRY_PIECES => {
+            pieces.push(piece);
+            Ok(PieceType::Fairy(pieces.len() as u8 - 1))
+        }
+        None => Err(FairyError::TooMany),
+    }
+}
+
+/// Defines all of `new` at once, or none of them if they don't fit.
+fn define_all(new: Vec<FairyPiece>) -> Result<Vec<PieceType>, FairyError> {
+    let mut defined = FAIRY_PIECES.write().unwrap();
+    let mut pieces = defined.as_slice().to_vec();
+    let ptypes = new
+        .into_iter()
+        .map(|piece| add_fairy_piece(&mut pieces, piece))
+        .collect::<Result<_, _>>()?;
+    *defined = FairyPieces(Some(pieces.into()));
+    Ok(ptypes)
+}
+
+/// Makes `piece` available to boards made from now on. A piece with the
+/// same letter as an earlier one replaces it.
+pub fn define_fairy_piece(piece: FairyPiece) -> Result<PieceType, FairyError> {
+    define_all(vec![piece]).map(|ptypes| ptypes[0])
+}
+
+#[derive(Deserialize)]
+struct PiecesFile {
+    #[serde(default)]
+    piece: Vec<PieceDefinition>,
+}
+
+/// A `[[piece]]` table. Other keys, like how the piece looks, are left to
+/// the game.
+#[derive(Deserialize)]
+struct PieceDefinition {
+    name: String,
+    letter: char,
+    betza: String,
+    value: i32,
+}
+
+/// Defines the pieces of a TOML pieces file, each a `[[piece]]` table with
+/// `name`, `letter`, `betza` and `value` keys. Nothing is defined if any of
+/// them is invalid.
+pub fn define_fairy_pieces(toml: &str) -> Result<Vec<PieceType>, FairyError> {
+    let file: PiecesFile =
+        toml::from_str(toml).map_err(|err| FairyError::Parse(err.to_string()))?;
+    let pieces = file
+        .piece
+        .iter()
+        .map(|def| FairyPiece::new(&def.name, def.letter, &def.betza, def.value))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (i, piece) in pieces.iter().enumerate() {
+        if pieces[..i].iter().any(|p| p.letter == piece.letter) {
+            return Err(FairyError::Letter(piece.name.clone(), piece.letter));
+        }
+    }
+    define_all(pieces)
+}
+
+/// The step of a single Betza atom, e.g. `(1, 2)` for the knight's `N`.
+fn betza_atom(c: char) -> Option<(i8, i8)> {
+    Some(match c {
+        'W' => (0, 1),
+        'F' => (1, 1),
+        'D' => (0, 2),
+        'N' => (1, 2),
+        'A' => (2, 2),
+        'H' => (0, 3),
+        'C' => (1, 3),
+        'Z' => (2, 3),
+        'G' => (3, 3),
+        _ => return None,
+    })
+}
+
+/// All eight ways to turn and mirror `step`, without duplicates.
+fn symmetric_steps((a, b): (i8, i8)) -> Vec<(i8, i8)> {
+    let mut steps = Vec::new();
+    for (x, y) in [(a, b), (b, a)] {
+        for step in [(x, y), (-x, y), (x, -y), (-x, -y)] {
+            if !steps.contains(&step) {
+                steps.push(step);
+            }
+        }
+    }
+    steps
+}
+
+/// Whether `step` is one of those picked by the direction `modifiers`. On
+/// orthogonal steps `fs` means forward and sideways, on others like `fl`
+/// on the ferz it means forward and left at once.
+fn in_directions((df, dr): (i8, i8), modifiers: &str) -> bool {
+    let has = |letters: &str| letters.chars().any(|c| modifiers.contains(c));
+    let vertical = has("fbv");
+    let horizontal = has("lrs");
+    let forward = dr > 0 && has("fv");
+    let backward = dr < 0 && has("bv");
+    let left = df < 0 && has("ls");
+    let right = df > 0 && has("rs");
+    if !vertical && !horizontal {
+        true
+    } else if df == 0 || dr == 0 {
+        forward || backward || left || right
+    } else {
+        (!vertical || forward || backward) && (!horizontal || left || right)
+    }
+}
+
+/// Parses movement written in Betza notation: atoms `W F D N A H C Z G`,
+/// the compounds `K R B Q`, a doubled atom or a number after it for a
+/// rider, and the lowercase prefixes `m` (move only), `c` (capture only),
+/// `g` (grasshopper hop) and the directions `f b l r s v`.
+pub fn parse_betza(betza: &str) -> Option<Vec<FairyMove>> {
+    let chars: Vec<char> = betza.chars().collect();
+    let mut moves = Vec::new();
+    let mut modifiers = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if c.is_ascii_lowercase() {
+            if !"mcgfblrsv".contains(c) {
+                return None;
+            }
+            modifiers.push(c);
+            continue;
+        }
+
+        let mut atoms = match c {
+            'K' => vec![((0, 1), Some(1)), ((1, 1), Some(1))],
+            'R' => vec![((0, 1), None)],
+            'B' => vec![((1, 1), None)],
+            'Q' => vec![((0, 1), None), ((1, 1), None)],
+            _ => vec![(betza_atom(c)?, Some(1))],
+        };
+        if betza_atom(c).is_some() {
+            if chars.get(i) == Some(&c) {
+                i += 1;
+                atoms[0].1 = None;
+            } else {
+                let digits = chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+                if digits > 0 {
+                    let range: String = chars[i..i + digits].iter().collect();
+                    atoms[0].1 = Some(range.parse().ok().filter(|&range| range > 0)?);
+                    i += digits;
+                }
+            }
+        }
+
+        let mode = match (modifiers.contains('m'), modifiers.contains('c')) {
+            (true, false) => MoveMode::MoveOnly,
+            (false, true) => MoveMode::CaptureOnly,
+            _ => MoveMode::MoveOrCapture,
+        };
+        for (atom, range) in atoms {
+            for step in symmetric_steps(atom) {
+                if in_directions(step, &modifiers) {
+                    moves.push(FairyMove {
+                        step,
+                        range,
+                        mode,
+                        hop: modifiers.contains('g'),
+                    });
+                }
+            }
+        }
+        modifiers.clear();
+    }
+    if moves.is_empty() || !modifiers.is_empty() {
+        return None;
+    }
+    Some(moves)
+}
@@ -0,0 +1,407 @@
+use super::*;
+
+use std::{error::Error, fmt, str::FromStr};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// A FEN needs at least the placement, side, castling and en passant fields.
+    FieldCount(usize),
+    /// There must be 1 to `MAX_RANKS` ranks.
+    RankCount(usize),
+    /// The Crazyhouse pocket after the placement, like `[Qnp]`.
+    Pocket(String),
+    /// A rank, numbered as on the board, does not describe as many squares
+    /// as the top one, or more than `MAX_FILES`.
+    RankLength {
+        rank: usize,
+        squares: usize,
+        files: usize,
+    },
+    UnknownPiece(char),
+    SideToMove(String),
+    Castling(String),
+    EnPassant(String),
+    HalfmoveClock(String),
+    /// The Three-check field, remaining checks like `3+2`.
+    Checks(String),
+    FullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => {
+                write!(f, "expected 4 to 7 space separated fields, found {}", n)
+            }
+            FenError::RankCount(n) => write!(
+                f,
+                "expected 1 to {} ranks separated by '/', found {}",
+                MAX_RANKS, n
+            ),
+            FenError::Pocket(s) => write!(f, "invalid pocket '{}'", s),
+            FenError::RankLength {
+                rank,
+                squares,
+                files,
+            } => write!(
+                f,
+                "rank {} describes {} squares instead of {}",
+                rank, squares, files
+            ),
+            FenError::UnknownPiece(c) => write!(f, "unknown piece letter '{}'", c),
+            FenError::SideToMove(s) => write!(f, "side to move must be 'w' or 'b', found '{}'", s),
+            FenError::Castling(s) => write!(f, "invalid castling availability '{}'", s),
+            FenError::EnPassant(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::HalfmoveClock(s) => write!(f, "invalid halfmove clock '{}'", s),
+            FenError::Checks(s) => write!(f, "invalid remaining checks '{}'", s),
+            FenError::FullmoveNumber(s) => write!(f, "invalid fullmove number '{}'", s),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+/// The piece written as `c`, fairy pieces being looked up in `fairy`.
+pub fn piece_from_char(c: char, fairy: &FairyPieces) -> Option<(PieceType, PieceColor)> {
+    let color = if c.is_ascii_uppercase() {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    };
+    let ptype = match c.to_ascii_lowercase() {
+        'k' => PieceType::King,
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'r' => PieceType::Rook,
+        'b' => PieceType::Bishop,
+        'q' => PieceType::Queen,
+        _ => fairy.by_letter(c)?,
+    };
+    Some((ptype, color))
+}
+
+pub fn piece_to_char(ptype: PieceType, color: PieceColor, fairy: &FairyPieces) -> char {
+    let c = match ptype {
+        PieceType::King => 'k',
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Rook => 'r',
+        PieceType::Bishop => 'b',
+        PieceType::Queen => 'q',
+        PieceType::Fairy(_) => fairy.get(ptype).map_or('?', |fairy| fairy.letter),
+    };
+    match color {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
+
+impl Board {
+    /// Parses a position in Forsyth-Edwards Notation. The halfmove clock and
+    /// fullmove number may be omitted and default to `0 1`.
+    ///
+    /// The variant extensions are understood as well and select their
+    /// variant: a Crazyhouse pocket like `[Qnp]` after the placement, with
+    /// `~` marking promoted pieces, and the remaining Three-check checks like
+    /// `3+2` after the en passant square.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+        let checks = match fields.get(4) {
+            Some(field) if field.contains('+') => Some(fields.remove(4)),
+            _ => None,
+        };
+        if !(4..=6).contains(&fields.len()) {
+            return Err(FenError::FieldCount(fields.len() + checks.iter().count()));
+        }
+
+        // Pieces are read with the definitions the board then keeps
+        let fairy = FairyPieces::current();
+        let mut variant_state = VariantState::default();
+        let placement = match fields[0].split_once('[') {
+            Some((placement, pocket)) => {
+                let invalid = || FenError::Pocket(pocket.to_string());
+                let pocket = pocket.strip_suffix(']').ok_or_else(invalid)?;
+                for c in pocket.chars() {
+                    match piece_from_char(c, &fairy) {
+                        Some((ptype, color)) if ptype != PieceType::King => {
+                            let count = variant_state.pocket(color, ptype);
+                            variant_state.set_pocket(color, ptype, count + 1);
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                placement
+            }
+            None => fields[0],
+        };
+
+        // The board is as high as there are ranks and as wide as the first
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() > MAX_RANKS as usize {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        let mut rows = Vec::new();
+        for (i, rank) in ranks.iter().enumerate() {
+            let mut row = Vec::new();
+            let mut promoted = Vec::new();
+            let mut chars = rank.chars().peekable();
+            while let Some(c) = chars.next() {
+                if let Some(mut skip) = c.to_digit(10) {
+                    // Past `MAX_FILES` the rank is too long whatever follows
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        skip = (skip * 10 + digit).min(MAX_FILES as u32 + 1);
+                        chars.next();
+                    }
+                    row.resize(row.len() + skip as usize, None);
+                } else if c == '~' {
+                    promoted.extend(row.len().checked_sub(1));
+                } else {
+                    let piece = piece_from_char(c, &fairy).ok_or(FenError::UnknownPiece(c))?;
+                    row.push(Some(piece));
+                }
+            }
+            let files = rows
+                .first()
+                .map_or(row.len(), |(first, _): &(Vec<_>, _)| first.len());
+            if row.len() != files || files == 0 || files > MAX_FILES as usize {
+                return Err(FenError::RankLength {
+                    rank: ranks.len() - i,
+                    squares: row.len(),
+                    files: files.min(MAX_FILES as usize),
+                });
+            }
+            rows.push((row, promoted));
+        }
+
+        let size = BoardSize::new(rows[0].0.len() as u8, rows.len() as u8)
+            .ok_or(FenError::RankCount(rows.len()))?;
+        let mut board = Board::empty_with_fairy_pieces(size, fairy);
+        for (i, (row, promoted)) in rows.into_iter().enumerate() {
+            let rank = size.ranks - 1 - i as u8;
+            for (file, piece) in row.into_iter().enumerate() {
+                board.set(Square::new(file as u8, rank), piece);
+            }
+            for file in promoted {
+                variant_state.promoted |= 1 << Square::new(file as u8, rank).index();
+            }
+        }
+        if fields[0].contains('[') {
+            board.variant = VariantKind::Crazyhouse;
+        }
+        board.variant_state = variant_state;
+
+        board.side_to_move = match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            s => return Err(FenError::SideToMove(s.to_string())),
+        };
+
+        // X-FEN: K and Q castle with the outermost rook, a file letter names
+        // the rook otherwise. Shredder-FEN always uses the letters. Boards
+        // narrower than 8 files have no castling, and every right needs its
+        // rook on the back rank beside the king.
+        if fields[2] != "-" {
+            let invalid = || FenError::Castling(fields[2].to_string());
+            if size.files < 8 {
+                return Err(invalid());
+            }
+            for c in fields[2].chars() {
+                let color = if c.is_ascii_uppercase() {
+                    PieceColor::White
+                } else {
+                    PieceColor::Black
+                };
+                let king = board
+                    .king_square(color)
+                    .filter(|king| king.rank == board.back_rank(color))
+                    .ok_or_else(invalid)?;
+                let last_file = size.files - 1;
+                let rook_file = match c.to_ascii_lowercase() {
+                    'k' => board.outermost_rook(color, true),
+                    'q' => board.outermost_rook(color, false),
+                    letter if letter.is_ascii_lowercase() => {
+                        board.chess960 = true;
+                        Some(letter as u8 - b'a')
+                    }
+                    _ => None,
+                }
+                .filter(|&file| {
+                    file < size.files
+                        && file != king.file
+                        && board.get(Square::new(file, king.rank)) == Some((PieceType::Rook, color))
+                })
+                .ok_or_else(invalid)?;
+                let king_side = rook_file > king.file;
+                board.castling.set(color, king_side, true);
+                board.castling.set_rook_file(color, king_side, rook_file);
+                let classical_rook = if king_side { last_file } else { 0 };
+                if king.file != size.files / 2 || rook_file != classical_rook {
+                    board.chess960 = true;
+                }
+            }
+        }
+
+        // The square the opponent's pawn just skipped, with the pawn in
+        // front of it, on boards where pawns push two squares
+        let pusher = board.side_to_move.opponent();
+        let skipped_rank = match pusher {
+            PieceColor::White => 2,
+            PieceColor::Black => size.ranks.saturating_sub(3),
+        };
+        let skipped = |square: Square| {
+            let pawn = board.offset(square, 0, pusher.forward());
+            board.has_double_pawn_push()
+                && square.rank == skipped_rank
+                && board.get(square).is_none()
+                && pawn.and_then(|pawn| board.get(pawn)) == Some((PieceType::Pawn, pusher))
+        };
+        let en_passant = match fields[3] {
+            "-" => None,
+            s => match Square::parse(s) {
+                Some(square) if size.contains(square) && skipped(square) => Some(square),
+                _ => return Err(FenError::EnPassant(s.to_string())),
+            },
+        };
+        board.en_passant = en_passant;
+
+        if let Some(s) = checks {
+            let invalid = || FenError::Checks(s.to_string());
+            let (white, black) = s.split_once('+').ok_or_else(invalid)?;
+            for (color, remaining) in [(PieceColor::White, white), (PieceColor::Black, black)] {
+                let remaining: u8 = remaining.parse().map_err(|_| invalid())?;
+                board.variant_state.checks[color as usize] =
+                    3u8.checked_sub(remaining).ok_or_else(invalid)?;
+            }
+            board.variant = VariantKind::ThreeCheck;
+        }
+
+        if let Some(s) = fields.get(4) {
+            board.halfmove_clock = s
+                .parse()
+                .map_err(|_| FenError::HalfmoveClock(s.to_string()))?;
+        }
+        if let Some(s) = fields.get(5) {
+            board.fullmove_number = match s.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::FullmoveNumber(s.to_string())),
+            };
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        let size = self.size();
+        for rank in (0..size.ranks).rev() {
+            let mut empty = 0;
+            for file in 0..size.files {
+                let square = Square::new(file, rank);
+                match self.get(square) {
+                    Some((ptype, color)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_to_char(ptype, color, self.fairy_pieces()));
+                        if self.variant_state.promoted & 1 << square.index() != 0 {
+                            fen.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        if self.variant == VariantKind::Crazyhouse {
+            fen.push('[');
+            for color in [PieceColor::White, PieceColor::Black] {
+                for ptype in DROP_PIECES.iter().rev().copied() {
+                    for _ in 0..self.variant_state.pocket(color, ptype) {
+                        fen.push(piece_to_char(ptype, color, self.fairy_pieces()));
+                    }
+                }
+            }
+            fen.push(']');
+        }
+
+        fen.push_str(match self.side_to_move {
+            PieceColor::White => " w ",
+            PieceColor::Black => " b ",
+        });
+
+        fen.push_str(&self.castling_field(false));
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&format!(" {}", square)),
+            None => fen.push_str(" -"),
+        }
+        if self.variant == VariantKind::ThreeCheck {
+            let checks = self.variant_state.checks;
+            fen.push_str(&format!(
+                " {}+{}",
+                3u8.saturating_sub(checks[PieceColor::White as usize]),
+                3u8.saturating_sub(checks[PieceColor::Black as usize])
+            ));
+        }
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
+    }
+
+    /// Like `to_fen`, but with the castling rooks always given by their file
+    /// as in Shredder-FEN, e.g. `HAha` for the classical position.
+    pub fn to_shredder_fen(&self) -> String {
+        let mut fields: Vec<String> = self.to_fen().split(' ').map(str::to_string).collect();
+        // Castling is the third field in every variant
+        fields[2] = self.castling_field(true);
+        fields.join(" ")
+    }
+
+    /// The X-FEN castling field, or the Shredder-FEN one with `shredder`.
+    fn castling_field(&self, shredder: bool) -> String {
+        let mut field = String::new();
+        for color in [PieceColor::White, PieceColor::Black] {
+            for king_side in [true, false] {
+                if !self.castling.get(color, king_side) {
+                    continue;
+                }
+                let file = self.castling.rook_file(color, king_side);
+                let c = if !shredder && self.outermost_rook(color, king_side) == Some(file) {
+                    if king_side {
+                        'k'
+                    } else {
+                        'q'
+                    }
+                } else {
+                    (b'a' + file) as char
+                };
+                field.push(match color {
+                    PieceColor::White => c.to_ascii_uppercase(),
+                    PieceColor::Black => c,
+                });
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+}
+
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Board::from_fen(s)
+    }
+}
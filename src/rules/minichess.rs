@@ -0,0 +1,59 @@
+use super::*;
+
+/// Gardner's minichess on 5x5, the classical pieces without one of each
+/// minor piece and rook.
+const GARDNER: [PieceType; 5] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+/// Los Alamos chess on 6x6, without bishops.
+const LOS_ALAMOS: [PieceType; 6] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Knight,
+    PieceType::Rook,
+];
+
+impl Board {
+    /// The starting position on a board of `size`: the classical one on
+    /// 8x8, Gardner's minichess on 5x5, Los Alamos chess on 6x6 and
+    /// Capablanca chess on 10x8. Other sizes have no standard setup and can
+    /// only be given as a FEN.
+    pub fn start_with_size(size: BoardSize) -> Option<Board> {
+        match (size.files, size.ranks) {
+            (8, 8) => Some(Board::start()),
+            (5, 5) => Some(Board::with_back_rank(&GARDNER, 5)),
+            (6, 6) => Some(Board::with_back_rank(&LOS_ALAMOS, 6)),
+            (10, 8) => Board::capablanca(),
+            _ => None,
+        }
+    }
+
+    /// Capablanca chess, with an archbishop `a` and a chancellor `c` from
+    /// the pieces file next to the bishops. `None` unless both are defined.
+    pub fn capablanca() -> Option<Board> {
+        let archbishop = fairy_piece('a')?;
+        let chancellor = fairy_piece('c')?;
+        Some(Board::with_back_rank(
+            &[
+                PieceType::Rook,
+                PieceType::Knight,
+                archbishop,
+                PieceType::Bishop,
+                PieceType::Queen,
+                PieceType::King,
+                PieceType::Bishop,
+                chancellor,
+                PieceType::Knight,
+                PieceType::Rook,
+            ],
+            8,
+        ))
+    }
+}
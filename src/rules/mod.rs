@@ -0,0 +1,245 @@
+//! Chess rules independent of Bevy: board representation, move generation
+//! and check detection. Everything in here is plain Rust so it can be driven
+//! (and tested) without opening a window.
+
+mod board;
+mod chess960;
+mod clock;
+mod fairy;
+mod fen;
+mod minichess;
+mod movegen;
+mod outcome;
+mod perft;
+mod pgn;
+mod san;
+mod variant;
+
+#[cfg(test)]
+mod tests;
+
+pub use board::*;
+pub use chess960::*;
+pub use clock::*;
+pub use fairy::*;
+pub use fen::*;
+pub use movegen::*;
+pub use outcome::*;
+pub use perft::*;
+pub use pgn::*;
+pub use san::*;
+pub use variant::*;
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceType {
+    King,
+    Pawn,
+    Knight,
+    Rook,
+    Bishop,
+    Queen,
+    /// A piece from a pieces file, numbered in the order it was defined.
+    /// Its moves are looked up with `Board::fairy`.
+    Fairy(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceColor {
+    Black,
+    White,
+}
+
+impl PieceColor {
+    pub fn opponent(self) -> Self {
+        match self {
+            PieceColor::Black => PieceColor::White,
+            PieceColor::White => PieceColor::Black,
+        }
+    }
+
+    /// Rank direction pawns of this color advance in.
+    pub fn forward(self) -> i8 {
+        match self {
+            PieceColor::Black => -1,
+            PieceColor::White => 1,
+        }
+    }
+}
+
+/// Most files and ranks a board can have, enough for 10x8 Capablanca chess
+/// or 10x10 Grand chess.
+pub const MAX_FILES: u8 = 10;
+pub const MAX_RANKS: u8 = 10;
+
+/// Width and height of a board in squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoardSize {
+    pub files: u8,
+    pub ranks: u8,
+}
+
+impl BoardSize {
+    pub const STANDARD: BoardSize = BoardSize { files: 8, ranks: 8 };
+
+    /// `None` unless the size fits in `MAX_FILES` by `MAX_RANKS`.
+    pub fn new(files: u8, ranks: u8) -> Option<BoardSize> {
+        if (1..=MAX_FILES).contains(&files) && (1..=MAX_RANKS).contains(&ranks) {
+            Some(BoardSize { files, ranks })
+        } else {
+            None
+        }
+    }
+
+    /// Parses a size written as files by ranks, e.g. `6x6` or `10x8`.
+    pub fn parse(s: &str) -> Option<BoardSize> {
+        let (files, ranks) = s.split_once('x')?;
+        BoardSize::new(files.parse().ok()?, ranks.parse().ok()?)
+    }
+
+    pub fn contains(self, square: Square) -> bool {
+        square.file < self.files && square.rank < self.ranks
+    }
+
+    /// Iterates over all squares, rank by rank starting at a1.
+    pub fn squares(self) -> impl Iterator<Item = Square> {
+        (0..self.ranks)
+            .flat_map(move |rank| (0..self.files).map(move |file| Square::new(file, rank)))
+    }
+}
+
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.files, self.ranks)
+    }
+}
+
+/// A square on the board. File 0 is the a-file, rank 0 is White's back rank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square {
+    pub file: u8,
+    pub rank: u8,
+}
+
+impl Square {
+    pub const fn new(file: u8, rank: u8) -> Self {
+        Square { file, rank }
+    }
+
+    /// Returns the square `df` files and `dr` ranks away, if it is on the
+    /// largest board. `Board::offset` also keeps it on the actual one.
+    pub fn offset(self, df: i8, dr: i8) -> Option<Square> {
+        let file = self.file as i8 + df;
+        let rank = self.rank as i8 + dr;
+        if (0..MAX_FILES as i8).contains(&file) && (0..MAX_RANKS as i8).contains(&rank) {
+            Some(Square::new(file as u8, rank as u8))
+        } else {
+            None
+        }
+    }
+
+    /// Parses algebraic notation such as `e4`, or `j10` on larger boards.
+    pub fn parse(s: &str) -> Option<Square> {
+        let mut chars = s.chars();
+        let file = chars.next().filter(char::is_ascii_lowercase)? as u8 - b'a';
+        let rank = chars.as_str();
+        if !rank.starts_with(|c: char| ('1'..='9').contains(&c)) {
+            return None;
+        }
+        let rank: u8 = rank.parse().ok()?;
+        if file >= MAX_FILES || rank > MAX_RANKS {
+            return None;
+        }
+        Some(Square::new(file, rank - 1))
+    }
+
+    /// Position in a table with room for the largest board, rank by rank.
+    pub fn index(self) -> usize {
+        self.rank as usize * MAX_FILES as usize + self.file as usize
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file) as char, self.rank + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    Quiet,
+    Capture,
+    DoublePawnPush,
+    EnPassant,
+    KingsideCastle,
+    QueensideCastle,
+    Promotion(PieceType),
+    PromotionCapture(PieceType),
+    /// A piece from the pocket put on the empty `to` square, as in
+    /// Crazyhouse. `from` is the same square.
+    Drop(PieceType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub kind: MoveKind,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square, kind: MoveKind) -> Self {
+        Move { from, to, kind }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::Capture | MoveKind::EnPassant | MoveKind::PromotionCapture(_)
+        )
+    }
+
+    pub fn is_castle(&self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::KingsideCastle | MoveKind::QueensideCastle
+        )
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        match self.kind {
+            MoveKind::Promotion(ptype) | MoveKind::PromotionCapture(ptype) => Some(ptype),
+            _ => None,
+        }
+    }
+
+    /// The piece dropped from the pocket, if this is a drop.
+    pub fn dropped(&self) -> Option<PieceType> {
+        match self.kind {
+            MoveKind::Drop(ptype) => Some(ptype),
+            _ => None,
+        }
+    }
+}
+
+/// Long algebraic notation, e.g. `e2e4` or `e7e8q`, drops as `N@f3`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ptype) = self.dropped() {
+            // A move doesn't know its board, a dropped fairy piece is named
+            // as defined now
+            let letter = piece_letter(ptype, &FairyPieces::current()).unwrap_or('P');
+            return write!(f, "{}@{}", letter, self.to);
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion() {
+            Some(PieceType::Queen) => write!(f, "q"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::King) => write!(f, "k"),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use super::*;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+pub const PROMOTION_PIECES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+impl Board {
+    /// All moves for the side to move that obey piece movement rules, without
+    /// checking whether they leave the own king in check.
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(64);
+        for (from, ptype, color) in self.pieces() {
+            if color == self.side_to_move {
+                self.piece_moves(from, ptype, color, &mut moves);
+            }
+        }
+        moves
+    }
+
+    /// All moves for the side to move under the rules of its variant.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.variant.rules().legal_moves(self)
+    }
+
+    /// All moves for the side to move that do not leave the own king in
+    /// check, the legal moves of standard chess.
+    pub fn king_safe_moves(&self) -> Vec<Move> {
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|&mv| self.leaves_king_safe(mv))
+            .collect()
+    }
+
+    /// Legal moves of the piece standing on `from`.
+    pub fn legal_moves_from(&self, from: Square) -> Vec<Move> {
+        if self.variant == VariantKind::Standard {
+            let mut moves = Vec::new();
+            if let Some((ptype, color)) = self.get(from) {
+                if color == self.side_to_move {
+                    self.piece_moves(from, ptype, color, &mut moves);
+                }
+            }
+            moves.retain(|&mv| self.leaves_king_safe(mv));
+            return moves;
+        }
+        self.legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == from && mv.dropped().is_none())
+            .collect()
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        match mv.dropped() {
+            Some(_) => self.legal_moves().contains(&mv),
+            None => self.legal_moves_from(mv.from).contains(&mv),
+        }
+    }
+
+    /// Whether the king of `color` is currently attacked.
+    pub fn in_check(&self, color: PieceColor) -> bool {
+        match self.king_square(color) {
+            Some(square) => self.is_square_attacked(square, color.opponent()),
+            None => false,
+        }
+    }
+
+    /// Whether any piece of color `by` attacks `square`.
+    pub fn is_square_attacked(&self, square: Square, by: PieceColor) -> bool {
+        let is = |sq: Option<Square>, ptypes: &[PieceType]| match sq.and_then(|sq| self.get(sq)) {
+            Some((ptype, color)) => color == by && ptypes.contains(&ptype),
+            None => false,
+        };
+
+        // A pawn of color `by` attacks diagonally forward, so look backwards
+        if is(self.offset(square, -1, -by.forward()), &[PieceType::Pawn])
+            || is(self.offset(square, 1, -by.forward()), &[PieceType::Pawn])
+        {
+            return true;
+        }
+        if KNIGHT_OFFSETS
+            .iter()
+            .any(|&(df, dr)| is(self.offset(square, df, dr), &[PieceType::Knight]))
+        {
+            return true;
+        }
+        if KING_OFFSETS
+            .iter()
+            .any(|&(df, dr)| is(self.offset(square, df, dr), &[PieceType::King]))
+        {
+            return true;
+        }
+
+        let slider = |directions: &[(i8, i8)], ptypes: &[PieceType]| {
+            directions.iter().any(|&(df, dr)| {
+                let mut current = self.offset(square, df, dr);
+                while let Some(sq) = current {
+                    if self.get(sq).is_some() {
+                        return is(Some(sq), ptypes);
+                    }
+                    current = self.offset(sq, df, dr);
+                }
+                false
+            })
+        };
+        slider(&ROOK_DIRECTIONS, &[PieceType::Rook, PieceType::Queen])
+            || slider(&BISHOP_DIRECTIONS, &[PieceType::Bishop, PieceType::Queen])
+            || self.fairy_attacks(square, by)
+    }
+
+    /// Whether a fairy piece of color `by` attacks `square`, found by
+    /// walking each of its capturing moves backwards from it.
+    fn fairy_attacks(&self, square: Square, by: PieceColor) -> bool {
+        for (ptype, fairy) in self.fairy_pieces().iter() {
+            let attacker = Some((ptype, by));
+            for fairy_move in fairy
+                .moves
+                .iter()
+                .filter(|fairy_move| fairy_move.mode.captures())
+            {
+                let (df, dr) = fairy_move.step_for(by);
+                let found = if fairy_move.hop {
+                    // The hurdle stands right before `square`, the hopper
+                    // somewhere behind it
+                    let mut back = self.line(square, (-df, -dr), None);
+                    match back.next() {
+                        Some(hurdle) if self.get(hurdle).is_some() => {
+                            let mut back = self.line(hurdle, (-df, -dr), fairy_move.range);
+                            back.find_map(|sq| self.get(sq))
+                        }
+                        _ => None,
+                    }
+                } else {
+                    self.line(square, (-df, -dr), fairy_move.range)
+                        .find_map(|sq| self.get(sq))
+                };
+                if found == attacker {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn leaves_king_safe(&self, mv: Move) -> bool {
+        let color = self.side_to_move;
+        let mut next = self.clone();
+        next.make_move(mv);
+        !next.in_check(color)
+    }
+
+    fn piece_moves(
+        &self,
+        from: Square,
+        ptype: PieceType,
+        color: PieceColor,
+        moves: &mut Vec<Move>,
+    ) {
+        match ptype {
+            PieceType::Pawn => self.pawn_moves(from, color, moves),
+            PieceType::Knight => self.step_moves(from, color, &KNIGHT_OFFSETS, moves),
+            PieceType::King => {
+                self.step_moves(from, color, &KING_OFFSETS, moves);
+                self.castling_moves(from, color, moves);
+            }
+            PieceType::Rook => self.slide_moves(from, color, &ROOK_DIRECTIONS, moves),
+            PieceType::Bishop => self.slide_moves(from, color, &BISHOP_DIRECTIONS, moves),
+            PieceType::Queen => {
+                self.slide_moves(from, color, &ROOK_DIRECTIONS, moves);
+                self.slide_moves(from, color, &BISHOP_DIRECTIONS, moves);
+            }
+            PieceType::Fairy(_) => {
+                if let Some(fairy) = self.fairy(ptype) {
+                    self.fairy_moves(from, color, fairy, moves);
+                }
+            }
+        }
+    }
+
+    fn step_moves(
+        &self,
+        from: Square,
+        color: PieceColor,
+        offsets: &[(i8, i8)],
+        moves: &mut Vec<Move>,
+    ) {
+        for &(df, dr) in offsets {
+            if let Some(to) = self.offset(from, df, dr) {
+                match self.get(to) {
+                    None => moves.push(Move::new(from, to, MoveKind::Quiet)),
+                    Some((_, c)) if c != color => {
+                        moves.push(Move::new(from, to, MoveKind::Capture))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn slide_moves(
+        &self,
+        from: Square,
+        color: PieceColor,
+        directions: &[(i8, i8)],
+        moves: &mut Vec<Move>,
+    ) {
+        for &(df, dr) in directions {
+            let mut current = self.offset(from, df, dr);
+            while let Some(to) = current {
+                match self.get(to) {
+                    None => moves.push(Move::new(from, to, MoveKind::Quiet)),
+                    Some((_, c)) => {
+                        if c != color {
+                            moves.push(Move::new(from, to, MoveKind::Capture));
+                        }
+                        break;
+                    }
+                }
+                current = self.offset(to, df, dr);
+            }
+        }
+    }
+
+    /// Squares in a line from `from`, not including it, up to `range` steps
+    /// or the edge of the board.
+    fn line(
+        &self,
+        from: Square,
+        (df, dr): (i8, i8),
+        range: Option<u8>,
+    ) -> impl Iterator<Item = Square> + '_ {
+        std::iter::successors(self.offset(from, df, dr), move |&sq| {
+            self.offset(sq, df, dr)
+        })
+        .take(range.map_or(usize::MAX, usize::from))
+    }
+
+    /// Moves of a fairy piece in each of its directions. Riders stop at the
+    /// first piece, hoppers jump it and land right behind.
+    fn fairy_moves(
+        &self,
+        from: Square,
+        color: PieceColor,
+        fairy: &FairyPiece,
+        moves: &mut Vec<Move>,
+    ) {
+        for fairy_move in &fairy.moves {
+            let (df, dr) = fairy_move.step_for(color);
+            let mode = fairy_move.mode;
+            let push = |to: Square, moves: &mut Vec<Move>| match self.get(to) {
+                None if mode.moves() => moves.push(Move::new(from, to, MoveKind::Quiet)),
+                Some((_, c)) if c != color && mode.captures() => {
+                    moves.push(Move::new(from, to, MoveKind::Capture))
+                }
+                _ => {}
+            };
+
+            let mut line = self.line(from, (df, dr), fairy_move.range);
+            if fairy_move.hop {
+                let landing = line
+                    .find(|&sq| self.get(sq).is_some())
+                    .and_then(|hurdle| self.offset(hurdle, df, dr));
+                if let Some(to) = landing {
+                    push(to, moves);
+                }
+                continue;
+            }
+            for to in line {
+                push(to, moves);
+                if self.get(to).is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Pawns only advance two squares on boards at least 8 ranks high, not
+    /// in minichess. Without that there is no en passant either.
+    pub(super) fn has_double_pawn_push(&self) -> bool {
+        self.size().ranks >= 8
+    }
+
+    fn pawn_moves(&self, from: Square, color: PieceColor, moves: &mut Vec<Move>) {
+        let forward = color.forward();
+        let promotion_rank = self.back_rank(color.opponent());
+        let start_rank = (self.back_rank(color) as i8 + forward) as u8;
+        let double_push = self.has_double_pawn_push();
+
+        let push = |to: Square, capture: bool, moves: &mut Vec<Move>| {
+            if to.rank == promotion_rank {
+                for &ptype in PROMOTION_PIECES.iter() {
+                    let kind = if capture {
+                        MoveKind::PromotionCapture(ptype)
+                    } else {
+                        MoveKind::Promotion(ptype)
+                    };
+                    moves.push(Move::new(from, to, kind));
+                }
+            } else {
+                let kind = if capture {
+                    MoveKind::Capture
+                } else {
+                    MoveKind::Quiet
+                };
+                moves.push(Move::new(from, to, kind));
+            }
+        };
+
+        if let Some(to) = self.offset(from, 0, forward) {
+            if self.get(to).is_none() {
+                push(to, false, moves);
+                if double_push && from.rank == start_rank {
+                    if let Some(to) = self.offset(to, 0, forward) {
+                        if self.get(to).is_none() {
+                            moves.push(Move::new(from, to, MoveKind::DoublePawnPush));
+                        }
+                    }
+                }
+            }
+        }
+
+        for df in [-1, 1] {
+            if let Some(to) = self.offset(from, df, forward) {
+                match self.get(to) {
+                    Some((_, c)) if c != color => push(to, true, moves),
+                    None if self.en_passant == Some(to) => {
+                        moves.push(Move::new(from, to, MoveKind::EnPassant))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Castling moves the king to the g or c file and the rook next to it,
+    /// wherever they started, or on wider boards to the second file from
+    /// the right instead of g. All squares either of them crosses must be
+    /// empty but for the two, and the king may not pass an attacked square.
+    fn castling_moves(&self, from: Square, color: PieceColor, moves: &mut Vec<Move>) {
+        let rank = self.back_rank(color);
+        let files = self.size().files;
+        if from.rank != rank {
+            return;
+        }
+        let enemy = color.opponent();
+        let between = |a: u8, b: u8| a.min(b)..=a.max(b);
+
+        for (king_side, king_to, rook_to, kind) in [
+            (true, files - 2, files - 3, MoveKind::KingsideCastle),
+            (false, 2, 3, MoveKind::QueensideCastle),
+        ] {
+            if !self.castling.get(color, king_side) {
+                continue;
+            }
+            let rook_from = self.castling.rook_file(color, king_side);
+            if self.get(Square::new(rook_from, rank)) != Some((PieceType::Rook, color)) {
+                continue;
+            }
+            let empty = between(from.file, king_to)
+                .chain(between(rook_from, rook_to))
+                .filter(|&file| file != from.file && file != rook_from)
+                .all(|file| self.get(Square::new(file, rank)).is_none());
+            let safe = || {
+                between(from.file, king_to)
+                    .all(|file| !self.is_square_attacked(Square::new(file, rank), enemy))
+            };
+            if empty && safe() {
+                moves.push(Move::new(from, Square::new(king_to, rank), kind));
+            }
+        }
+    }
+}
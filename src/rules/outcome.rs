@@ -0,0 +1,200 @@
+use super::*;
+
+/// Why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    ThreefoldRepetition,
+    /// Claimed by a player after 50 moves without capture or pawn move.
+    FiftyMoveRule,
+    /// Automatic after 75 moves without capture or pawn move.
+    SeventyFiveMoveRule,
+    InsufficientMaterial,
+    Resignation,
+    DrawAgreement,
+    TimeForfeit,
+    /// A king reached the centre in King of the Hill.
+    KingOfTheHill,
+    ThreeChecks,
+    /// A king blew up in Atomic.
+    KingExploded,
+    /// A side lost all its pieces, in Horde or Antichess.
+    NoPiecesLeft,
+}
+
+impl Termination {
+    pub fn description(self) -> &'static str {
+        match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::ThreefoldRepetition => "threefold repetition",
+            Termination::FiftyMoveRule => "fifty-move rule",
+            Termination::SeventyFiveMoveRule => "seventy-five-move rule",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::Resignation => "resignation",
+            Termination::DrawAgreement => "draw agreed",
+            Termination::TimeForfeit => "time forfeit",
+            Termination::KingOfTheHill => "king reached the centre",
+            Termination::ThreeChecks => "third check",
+            Termination::KingExploded => "king exploded",
+            Termination::NoPiecesLeft => "no pieces left",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+impl Outcome {
+    pub fn draw(termination: Termination) -> Self {
+        Outcome {
+            result: GameResult::Draw,
+            termination,
+        }
+    }
+
+    pub fn win(winner: PieceColor, termination: Termination) -> Self {
+        Outcome {
+            result: GameResult::win_for(winner),
+            termination,
+        }
+    }
+}
+
+/// The parts of a position that decide whether it repeats. An en passant
+/// square only counts while the capture is actually possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct RepetitionKey {
+    board: Board,
+}
+
+impl RepetitionKey {
+    pub(super) fn new(board: &Board) -> Self {
+        let mut board = board.clone();
+        if !board
+            .legal_moves()
+            .iter()
+            .any(|mv| mv.kind == MoveKind::EnPassant)
+        {
+            board.en_passant = None;
+        }
+        board.halfmove_clock = 0;
+        board.fullmove_number = 1;
+        RepetitionKey { board }
+    }
+}
+
+impl Board {
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check(self.side_to_move) && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check(self.side_to_move) && self.legal_moves().is_empty()
+    }
+
+    /// Whether `color` still has pieces that could deliver mate in some
+    /// sequence of legal moves.
+    pub fn has_mating_material(&self, color: PieceColor) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = [false; 2];
+        for (square, ptype, c) in self.pieces() {
+            if c != color {
+                continue;
+            }
+            match ptype {
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return true,
+                // Not worth working out for every possible fairy piece
+                PieceType::Fairy(_) => return true,
+                PieceType::Knight => knights += 1,
+                PieceType::Bishop => {
+                    bishop_square_colors[usize::from((square.file + square.rank) % 2)] = true
+                }
+                PieceType::King => {}
+            }
+        }
+        let bishops = bishop_square_colors.iter().filter(|&&any| any).count();
+        // Bishops on both colors or a knight with another minor mate alone
+        if bishops == 2 || (knights > 0 && knights + bishops >= 2) {
+            return true;
+        }
+        // A lone minor piece, or bishops all on one color, can only mate
+        // with help from enemy pieces
+        let opponent_pieces = self
+            .pieces()
+            .filter(|&(_, ptype, c)| c != color && ptype != PieceType::King)
+            .count();
+        knights + bishops > 0 && opponent_pieces > 0
+    }
+
+    /// Whether neither side can possibly checkmate: lone kings, a single
+    /// minor piece, or only bishops all on squares of the same color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = Vec::new();
+        for (square, ptype, _) in self.pieces() {
+            match ptype {
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen | PieceType::Fairy(_) => {
+                    return false
+                }
+                PieceType::Knight => knights += 1,
+                PieceType::Bishop => bishop_square_colors.push((square.file + square.rank) % 2),
+                PieceType::King => {}
+            }
+        }
+        let minors = knights + bishop_square_colors.len();
+        minors <= 1
+            || (knights == 0
+                && bishop_square_colors
+                    .iter()
+                    .all(|&color| color == bishop_square_colors[0]))
+    }
+}
+
+impl GameRecord {
+    /// How often the position after the last move occurred in the game.
+    pub fn repetitions(&self) -> usize {
+        let start = RepetitionKey::new(&self.start);
+        let last = self.moves.last().map_or(&start, |recorded| &recorded.key);
+        std::iter::once(&start)
+            .chain(self.moves.iter().map(|recorded| &recorded.key))
+            .filter(|&key| key == last)
+            .count()
+    }
+
+    /// The outcome forced by the rules in the current position, if any.
+    /// `board` is the position after the last recorded move.
+    pub fn automatic_outcome(&self, board: &Board) -> Option<Outcome> {
+        let rules = board.variant.rules();
+        if let Some(outcome) = rules.decisive_outcome(board) {
+            return Some(outcome);
+        }
+        if board.legal_moves().is_empty() {
+            return Some(rules.no_moves_outcome(board));
+        }
+        if rules.insufficient_material(board) {
+            return Some(Outcome::draw(Termination::InsufficientMaterial));
+        }
+        if self.repetitions() >= 3 {
+            return Some(Outcome::draw(Termination::ThreefoldRepetition));
+        }
+        if board.halfmove_clock >= 150 {
+            return Some(Outcome::draw(Termination::SeventyFiveMoveRule));
+        }
+        None
+    }
+
+    /// A draw a player may claim in `board` instead of waiting for an
+    /// agreement.
+    pub fn claimable_draw(&self, board: &Board) -> Option<Outcome> {
+        if board.halfmove_clock >= 100 {
+            Some(Outcome::draw(Termination::FiftyMoveRule))
+        } else {
+            None
+        }
+    }
+}
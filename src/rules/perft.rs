@@ -0,0 +1,37 @@
+use super::*;
+
+/// Counts the leaf nodes of the legal move tree `depth` plies deep.
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .into_iter()
+        .map(|mv| {
+            let mut next = board.clone();
+            next.make_move(mv);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+/// `perft` split up by root move, for finding where a move generator goes
+/// wrong by comparing against a reference engine.
+pub fn divide(board: &Board, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut next = board.clone();
+            next.make_move(mv);
+            (mv, perft(&next, depth - 1))
+        })
+        .collect()
+}
@@ -0,0 +1,375 @@
+use super::*;
+
+use std::{error::Error, fmt, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Ongoing,
+}
+
+impl GameResult {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Ongoing => "*",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<GameResult> {
+        match s {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Ongoing),
+            _ => None,
+        }
+    }
+
+    pub fn win_for(color: PieceColor) -> GameResult {
+        match color {
+            PieceColor::White => GameResult::WhiteWins,
+            PieceColor::Black => GameResult::BlackWins,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// A `[Name "Value"]` tag pair could not be read.
+    Tag(String),
+    Fen(FenError),
+    /// The move at the given ply (counted from 1) is not legal.
+    Move {
+        ply: usize,
+        error: SanError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Tag(line) => write!(f, "malformed tag pair '{}'", line),
+            PgnError::Fen(err) => write!(f, "invalid FEN tag: {}", err),
+            PgnError::Move { ply, error } => write!(f, "ply {}: {}", ply, error),
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedMove {
+    pub mv: Move,
+    pub san: String,
+    /// Time left on the mover's clock after the move, saved as `%clk`.
+    pub clock: Option<Duration>,
+    /// Pieces the move took off the board, those blown up in Atomic
+    /// included.
+    pub captured: Vec<(PieceType, PieceColor)>,
+    /// The position after the move, to count repetitions.
+    pub(super) key: RepetitionKey,
+}
+
+/// A game as a starting position plus the moves played from it.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    /// Tag pairs in output order. The seven tag roster is always present.
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub moves: Vec<RecordedMove>,
+    pub result: GameResult,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        GameRecord::new(Board::start())
+    }
+}
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+impl GameRecord {
+    pub fn new(start: Board) -> Self {
+        GameRecord {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            start,
+            moves: Vec::new(),
+            result: GameResult::Ongoing,
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Records `mv`, which must be legal in `board`, the position before it.
+    pub fn push(&mut self, board: &Board, mv: Move) {
+        let san = board.to_san(mv);
+        let captured = board
+            .piece_changes(mv)
+            .into_iter()
+            .filter_map(|change| match change {
+                PieceChange::Removed(square) => board.get(square),
+                _ => None,
+            })
+            .collect();
+        let mut after = board.clone();
+        after.make_move(mv);
+        self.moves.push(RecordedMove {
+            mv,
+            san,
+            clock: None,
+            captured,
+            key: RepetitionKey::new(&after),
+        });
+    }
+
+    /// The position after the first `ply` moves.
+    pub fn board_at(&self, ply: usize) -> Board {
+        let mut board = self.start.clone();
+        for recorded in self.moves.iter().take(ply) {
+            board.make_move(recorded.mv);
+        }
+        board
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        let mut tags = self.tags.clone();
+        if let Some((_, value)) = tags.iter_mut().find(|(n, _)| n == "Result") {
+            *value = self.result.as_str().to_string();
+        }
+        if self.tag("Variant").is_none() {
+            if self.start.variant != VariantKind::Standard {
+                tags.push(("Variant".to_string(), self.start.variant.name().to_string()));
+            } else if self.start.chess960 {
+                tags.push(("Variant".to_string(), "Chess960".to_string()));
+            }
+        }
+        if self.start != Board::start() {
+            tags.retain(|(n, _)| n != "SetUp" && n != "FEN");
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), self.start.to_fen()));
+        }
+        for (name, value) in &tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut number = self.start.fullmove_number;
+        let mut color = self.start.side_to_move;
+        for (i, recorded) in self.moves.iter().enumerate() {
+            if color == PieceColor::White {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(recorded.san.clone());
+            if let Some(clock) = recorded.clock {
+                tokens.push(format!("{{[%clk {}]}}", format_clock(clock)));
+            }
+            if color == PieceColor::Black {
+                number += 1;
+            }
+            color = color.opponent();
+        }
+        tokens.push(self.result.as_str().to_string());
+
+        // Export format keeps movetext lines below 80 characters
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 79 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Reads the first game in `pgn`. Comments, variations and numeric
+    /// annotation glyphs are skipped.
+    pub fn from_pgn(pgn: &str) -> Result<GameRecord, PgnError> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+        for line in pgn.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && movetext.trim().is_empty() {
+                tags.push(parse_tag(trimmed).ok_or_else(|| PgnError::Tag(trimmed.to_string()))?);
+            } else if !trimmed.starts_with('%') {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        let mut start = match tags.iter().find(|(n, _)| n == "FEN") {
+            Some((_, fen)) => Board::from_fen(fen).map_err(PgnError::Fen)?,
+            None => Board::start(),
+        };
+        if let Some((_, variant)) = tags.iter().find(|(n, _)| n == "Variant") {
+            if let Some(kind) = VariantKind::parse(variant) {
+                // Without a FEN tag the game starts from the variant's setup
+                if tags.iter().any(|(n, _)| n == "FEN") {
+                    start.variant = kind;
+                } else if let Some(setup) = kind.setup(start.clone()) {
+                    start = setup;
+                }
+            }
+            let variant = variant.to_ascii_lowercase();
+            start.chess960 |= variant == "chess960" || variant == "fischerandom";
+        }
+        let mut record = GameRecord::new(start);
+        for (name, value) in &tags {
+            record.set_tag(name, value);
+        }
+        record.result = record
+            .tag("Result")
+            .and_then(GameResult::parse)
+            .unwrap_or(GameResult::Ongoing);
+
+        let mut board = record.start.clone();
+        for token in movetext_tokens(&movetext) {
+            let token = match token {
+                Token::Comment(comment) => {
+                    if let Some(last) = record.moves.last_mut() {
+                        last.clock = last.clock.or_else(|| clock_command(&comment));
+                    }
+                    continue;
+                }
+                Token::Move(token) => token,
+            };
+            if let Some(result) = GameResult::parse(&token) {
+                record.result = result;
+                break;
+            }
+            let mv = board.parse_san(&token).map_err(|error| PgnError::Move {
+                ply: record.moves.len() + 1,
+                error,
+            })?;
+            record.push(&board, mv);
+            board.make_move(mv);
+        }
+        Ok(record)
+    }
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+enum Token {
+    Move(String),
+    Comment(String),
+}
+
+/// The time in a `[%clk h:mm:ss]` command inside a comment.
+fn clock_command(comment: &str) -> Option<Duration> {
+    let start = comment.find("[%clk")? + "[%clk".len();
+    let end = start + comment[start..].find(']')?;
+    parse_clock(comment[start..end].trim())
+}
+
+/// `token` without a leading move number like `12.` or `12...`. Digits
+/// not followed by a dot are kept, they may be castling written `0-0`.
+fn strip_move_number(token: &str) -> &str {
+    let rest = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() < token.len() && rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+/// Splits movetext into SAN moves, the result token and brace comments,
+/// dropping move numbers, line comments, variations and NAGs.
+fn movetext_tokens(movetext: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut chars = movetext.chars();
+
+    let flush = |current: &mut String, tokens: &mut Vec<Token>| {
+        let token = if GameResult::parse(current).is_some() {
+            current.as_str()
+        } else {
+            strip_move_number(current)
+        };
+        if !token.is_empty() && !token.starts_with('$') {
+            tokens.push(Token::Move(token.to_string()));
+        }
+        current.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                flush(&mut current, &mut tokens);
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                if depth == 0 {
+                    tokens.push(Token::Comment(comment));
+                }
+            }
+            ';' => {
+                flush(&mut current, &mut tokens);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                flush(&mut current, &mut tokens);
+                depth += 1;
+            }
+            ')' => {
+                current.clear();
+                depth -= 1;
+            }
+            _ if depth > 0 => {}
+            c if c.is_whitespace() => flush(&mut current, &mut tokens),
+            c => current.push(c),
+        }
+    }
+    flush(&mut current, &mut tokens);
+    tokens
+}
@@ -0,0 +1,146 @@
+use super::*;
+
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    /// No legal move in the position is written this way.
+    NoMatch(String),
+    /// More than one legal move is written this way.
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::NoMatch(san) => write!(f, "'{}' is not a legal move", san),
+            SanError::Ambiguous(san) => write!(f, "'{}' is ambiguous", san),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+/// The uppercase letter SAN writes `ptype` with, `None` for pawns. Fairy
+/// pieces are looked up in `fairy`.
+pub fn piece_letter(ptype: PieceType, fairy: &FairyPieces) -> Option<char> {
+    match ptype {
+        PieceType::King => Some('K'),
+        PieceType::Pawn => None,
+        PieceType::Knight => Some('N'),
+        PieceType::Rook => Some('R'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Queen => Some('Q'),
+        PieceType::Fairy(_) => Some(
+            fairy
+                .get(ptype)
+                .map_or('?', |fairy| fairy.letter.to_ascii_uppercase()),
+        ),
+    }
+}
+
+impl Board {
+    /// Standard Algebraic Notation for the legal move `mv`, including the
+    /// check or mate suffix.
+    pub fn to_san(&self, mv: Move) -> String {
+        let mut san = self.san_without_suffix(mv);
+        let mut next = self.clone();
+        next.make_move(mv);
+        if next.in_check(next.side_to_move) {
+            san.push(if next.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    /// Finds the legal move written as `san`. Check, mate and annotation
+    /// suffixes are ignored, as is a missing `=` before the promotion piece
+    /// or a missing `P` before a pawn drop.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let wanted = normalize_san(san);
+        let mut found = None;
+        for mv in self.legal_moves() {
+            if normalize_san(&self.san_without_suffix(mv)) == wanted {
+                if found.is_some() {
+                    return Err(SanError::Ambiguous(san.to_string()));
+                }
+                found = Some(mv);
+            }
+        }
+        found.ok_or_else(|| SanError::NoMatch(san.to_string()))
+    }
+
+    fn san_without_suffix(&self, mv: Move) -> String {
+        match mv.kind {
+            MoveKind::KingsideCastle => return "O-O".to_string(),
+            MoveKind::QueensideCastle => return "O-O-O".to_string(),
+            // Written like in UCI, `N@f3` or `P@e4`
+            MoveKind::Drop(_) => return mv.to_string(),
+            _ => {}
+        }
+
+        let ptype = match self.get(mv.from) {
+            Some((ptype, _)) => ptype,
+            None => return mv.to_string(),
+        };
+
+        let mut san = String::new();
+        match piece_letter(ptype, self.fairy_pieces()) {
+            Some(letter) => {
+                san.push(letter);
+                san.push_str(&self.disambiguation(mv, ptype));
+            }
+            None if mv.is_capture() => san.push((b'a' + mv.from.file) as char),
+            None => {}
+        }
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+        if let Some(promoted) = mv.promotion() {
+            san.push('=');
+            san.extend(piece_letter(promoted, self.fairy_pieces()));
+        }
+        san
+    }
+
+    fn disambiguation(&self, mv: Move, ptype: PieceType) -> String {
+        let others: Vec<Square> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && self.get(other.from).map(|(p, _)| p) == Some(ptype)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        let file = (b'a' + mv.from.file) as char;
+        let rank = mv.from.rank + 1;
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|sq| sq.file != mv.from.file) {
+            file.to_string()
+        } else if others.iter().all(|sq| sq.rank != mv.from.rank) {
+            rank.to_string()
+        } else {
+            format!("{}{}", file, rank)
+        }
+    }
+}
+
+fn normalize_san(san: &str) -> String {
+    let san = san
+        .trim_end_matches(&['+', '#', '!', '?'][..])
+        .replace('0', "O")
+        .replace('=', "");
+    if san.starts_with('@') {
+        format!("P{}", san)
+    } else {
+        san
+    }
+}
@@ -0,0 +1,525 @@
+use super::*;
+
+use std::time::Duration;
+
+fn board(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+fn square(s: &str) -> Square {
+    Square::parse(s).unwrap()
+}
+
+fn moves_from(board: &Board, from: &str) -> Vec<Move> {
+    board
+        .legal_moves()
+        .into_iter()
+        .filter(|mv| mv.from == square(from))
+        .collect()
+}
+
+fn castles(board: &Board) -> Vec<MoveKind> {
+    board
+        .legal_moves()
+        .into_iter()
+        .filter(Move::is_castle)
+        .map(|mv| mv.kind)
+        .collect()
+}
+
+#[test]
+fn check_detection() {
+    assert!(board("4k3/8/8/8/8/8/8/4K2r w - - 0 1").in_check(PieceColor::White));
+    assert!(board("4k3/8/8/8/8/5n2/8/4K3 w - - 0 1").in_check(PieceColor::White));
+    assert!(board("4k3/8/8/8/8/8/5p2/4K3 w - - 0 1").in_check(PieceColor::White));
+    assert!(board("4k3/8/8/b7/8/8/8/4K3 w - - 0 1").in_check(PieceColor::White));
+    // Blocked sliders and pawns attacking the other way don't give check
+    assert!(!board("4k3/8/8/8/8/8/8/4KN1r w - - 0 1").in_check(PieceColor::White));
+    assert!(!board("4k3/8/8/8/8/8/8/3pKp2 w - - 0 1").in_check(PieceColor::White));
+    assert!(!board("4k3/8/8/8/8/8/8/4K3 w - - 0 1").in_check(PieceColor::Black));
+}
+
+#[test]
+fn checkmate_and_stalemate() {
+    // Back rank mate
+    let mated = board("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+    assert!(mated.legal_moves().is_empty());
+    assert!(mated.in_check(PieceColor::Black));
+    let stalemated = board("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1");
+    assert!(stalemated.legal_moves().is_empty());
+    assert!(!stalemated.in_check(PieceColor::Black));
+}
+
+#[test]
+fn pinned_pieces() {
+    // A pinned knight can't move at all
+    assert!(moves_from(&board("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1"), "e2").is_empty());
+    // A pinned rook only moves along the pin, up to taking the pinner
+    let mut rook_moves: Vec<String> = moves_from(&board("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1"), "e2")
+        .iter()
+        .map(Move::to_string)
+        .collect();
+    rook_moves.sort();
+    assert_eq!(rook_moves, ["e2e3", "e2e4", "e2e5", "e2e6", "e2e7"]);
+    // A diagonally pinned bishop keeps to the diagonal
+    let bishop = moves_from(&board("4k3/8/8/b7/8/8/3B4/4K3 w - - 0 1"), "d2");
+    assert!(bishop
+        .iter()
+        .all(|mv| mv.to.file + mv.to.rank == square("d2").file + square("d2").rank));
+    assert_eq!(bishop.len(), 3);
+}
+
+#[test]
+fn king_moves_out_of_check() {
+    // The king may not step along the line of the checking rook
+    let king = moves_from(&board("4k3/8/8/8/8/8/8/r3K3 w - - 0 1"), "e1");
+    let targets: Vec<Square> = king.iter().map(|mv| mv.to).collect();
+    assert!(!targets.contains(&square("f1")));
+    assert!(!targets.contains(&square("d1")));
+    assert!(targets.contains(&square("e2")));
+}
+
+#[test]
+fn castling() {
+    assert_eq!(
+        castles(&board("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1")),
+        [MoveKind::KingsideCastle, MoveKind::QueensideCastle]
+    );
+    // Not out of check
+    assert!(castles(&board("4k3/4r3/8/8/8/8/8/R3K2R w KQ - 0 1")).is_empty());
+    // Not through or into an attacked square
+    assert_eq!(
+        castles(&board("4k3/5r2/8/8/8/8/8/R3K2R w KQ - 0 1")),
+        [MoveKind::QueensideCastle]
+    );
+    assert_eq!(
+        castles(&board("4k3/2r5/8/8/8/8/8/R3K2R w KQ - 0 1")),
+        [MoveKind::KingsideCastle]
+    );
+    // Only the king's path counts, the rook may cross an attacked b1
+    assert_eq!(
+        castles(&board("4k3/1r6/8/8/8/8/8/R3K2R w KQ - 0 1")),
+        [MoveKind::KingsideCastle, MoveKind::QueensideCastle]
+    );
+    // Not with a piece in between or without the right
+    assert_eq!(
+        castles(&board("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1")),
+        [MoveKind::KingsideCastle]
+    );
+    assert_eq!(
+        castles(&board("4k3/8/8/8/8/8/8/R3K2R w Q - 0 1")),
+        [MoveKind::QueensideCastle]
+    );
+}
+
+#[test]
+fn en_passant() {
+    let captures = |fen: &str| -> Vec<Move> {
+        board(fen)
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.kind == MoveKind::EnPassant)
+            .collect()
+    };
+    assert_eq!(
+        captures("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"),
+        [Move::new(square("e5"), square("d6"), MoveKind::EnPassant)]
+    );
+    // Only right after the double push
+    assert!(captures("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").is_empty());
+    // Taking would leave both pawns off the rank the king is checked along
+    assert!(captures("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1").is_empty());
+
+    let mut after = board("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    after.make_move(Move::new(square("e5"), square("d6"), MoveKind::EnPassant));
+    assert_eq!(after.get(square("d5")), None);
+    assert_eq!(
+        after.get(square("d6")),
+        Some((PieceType::Pawn, PieceColor::White))
+    );
+}
+
+#[test]
+fn promotion() {
+    let promotions = |fen: &str, from: &str| -> Vec<MoveKind> {
+        moves_from(&board(fen), from)
+            .into_iter()
+            .map(|mv| mv.kind)
+            .collect()
+    };
+    assert_eq!(
+        promotions("8/P7/8/8/8/8/8/k1K5 w - - 0 1", "a7"),
+        PROMOTION_PIECES.map(MoveKind::Promotion)
+    );
+    let captures = promotions("1r6/P7/8/8/8/8/8/k1K5 w - - 0 1", "a7");
+    assert_eq!(captures.len(), 8);
+    for ptype in PROMOTION_PIECES {
+        assert!(captures.contains(&MoveKind::PromotionCapture(ptype)));
+    }
+    // Black promotes on the first rank
+    assert_eq!(
+        promotions("K7/8/8/8/8/8/7p/k7 b - - 0 1", "h2"),
+        PROMOTION_PIECES.map(MoveKind::Promotion)
+    );
+
+    let mut promoted = board("8/P7/8/8/8/8/8/k1K5 w - - 0 1");
+    promoted.make_move(Move::new(
+        square("a7"),
+        square("a8"),
+        MoveKind::Promotion(PieceType::Knight),
+    ));
+    assert_eq!(
+        promoted.get(square("a8")),
+        Some((PieceType::Knight, PieceColor::White))
+    );
+}
+
+#[test]
+fn en_passant_square_in_fen() {
+    assert!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").is_ok());
+    assert!(Board::from_fen("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1").is_ok());
+    // The rank of the wrong side, nothing pushed, or no room for it
+    assert!(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - e3 0 1").is_err());
+    assert!(Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1").is_err());
+    assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - d6 0 1").is_err());
+    assert!(Board::from_fen("4k3/8/3n4/3p4/8/8/8/4K3 w - d6 0 1").is_err());
+    // Pawns don't push two squares on a minichess board
+    assert!(Board::from_fen("k4/5/5/2p2/K4 w - c3 0 1").is_err());
+    assert!(Board::from_fen("k4/5/5/2p2/K4 w - - 0 1").is_ok());
+}
+
+#[test]
+fn long_ranks_in_fen() {
+    assert_eq!(
+        Board::from_fen("99999999999/8/8/8/8/8/8/8 w - - 0 1").map(|_| ()),
+        Err(FenError::RankLength {
+            rank: 8,
+            squares: MAX_FILES as usize + 1,
+            files: MAX_FILES as usize,
+        })
+    );
+    assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K99999999999 w - - 0 1").is_err());
+}
+
+#[test]
+fn huge_clock_values() {
+    assert_eq!(parse_clock("1:02:03"), Some(Duration::from_secs(3723)));
+    assert_eq!(parse_clock("1e30"), None);
+    assert_eq!(parse_clock("-5"), None);
+    assert_eq!(parse_clock("inf"), None);
+
+    let mut clock = ChessClock::new(TimeControl::fischer(Duration::MAX, Duration::from_secs(2)));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), Duration::MAX);
+}
+
+#[test]
+fn san_round_trip() {
+    let positions = [
+        // Castling both ways
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        // Promotions with and without capture
+        "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+        // Knights and rooks needing file, rank or both to tell them apart
+        "4k3/8/8/1N3N2/8/1N3N2/8/R3K2R w - - 0 1",
+        "4k3/R7/8/8/8/8/8/R3K3 w - - 0 1",
+    ];
+    for fen in positions {
+        let position = board(fen);
+        for mv in position.legal_moves() {
+            assert_eq!(position.parse_san(&position.to_san(mv)), Ok(mv), "{}", fen);
+        }
+    }
+
+    let knights = board("4k3/8/8/1N3N2/8/1N3N2/8/R3K2R w - - 0 1");
+    let san =
+        |from: &str, to: &str| knights.to_san(Move::new(square(from), square(to), MoveKind::Quiet));
+    assert_eq!(san("b5", "d4"), "Nb5d4");
+    assert_eq!(san("b5", "d6"), "Nbd6+");
+    assert!(knights.parse_san("Nd4").is_err());
+    let rooks = board("4k3/R7/8/8/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(
+        rooks.to_san(Move::new(square("a1"), square("a4"), MoveKind::Quiet)),
+        "R1a4"
+    );
+    let castles = board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_eq!(castles.parse_san("0-0"), castles.parse_san("O-O"));
+    assert_eq!(
+        castles.parse_san("O-O-O+").map(|mv| mv.kind),
+        Ok(MoveKind::QueensideCastle)
+    );
+    let promotes = board("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(
+        promotes.parse_san("axb8=N").map(|mv| mv.kind),
+        Ok(MoveKind::PromotionCapture(PieceType::Knight))
+    );
+    assert_eq!(promotes.parse_san("a8Q"), promotes.parse_san("a8=Q"));
+}
+
+#[test]
+fn pgn_round_trip() {
+    let pgn = "[Event \"Test\"]\n\
+        [Result \"1-0\"]\n\
+        \n\
+        1. e4 $1 e5 {Open game} 2. Nf3 (2. f4 exf4 {gambit} (2... d5)) 2... Nc6\n\
+        3. Bc4 Nf6 4. 0-0 Bc5 5. d3 0-0 ; castled\n\
+        6. Bg5 d6 7. Nc3!? h6 8. Bh4 g5?! 1-0\n";
+    let record = GameRecord::from_pgn(pgn).unwrap();
+    let sans: Vec<&str> = record.moves.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(
+        sans,
+        [
+            "e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O", "Bc5", "d3", "O-O", "Bg5", "d6", "Nc3",
+            "h6", "Bh4", "g5"
+        ]
+    );
+    assert_eq!(record.result, GameResult::WhiteWins);
+
+    let again = GameRecord::from_pgn(&record.to_pgn()).unwrap();
+    assert_eq!(again.moves, record.moves);
+    assert_eq!(again.tag("Event"), Some("Test"));
+
+    // Promotion from a set up position, which goes to the FEN tag
+    let mut promoted = GameRecord::new(board("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1"));
+    let start = promoted.start.clone();
+    promoted.push(&start, start.parse_san("axb8=Q+").unwrap());
+    let again = GameRecord::from_pgn(&promoted.to_pgn()).unwrap();
+    assert_eq!(again.start, promoted.start);
+    assert_eq!(again.moves, promoted.moves);
+}
+
+#[test]
+fn castling_rights_need_rooks() {
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").is_ok());
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1").is_ok());
+    // No rook on that side, a letter naming an empty square or another
+    // piece, or the king's own file
+    assert!(Board::from_fen("r3k3/8/8/8/8/8/8/R3K2R w KQk - 0 1").is_err());
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K3 w Kkq - 0 1").is_err());
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w G - 0 1").is_err());
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w B - 0 1").is_err());
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w E - 0 1").is_err());
+    assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Hh - 0 1").is_ok());
+}
+
+fn secs(s: u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+#[test]
+fn clock_increment() {
+    let mut clock = ChessClock::new(TimeControl::fischer(secs(60), secs(2)));
+    clock.tick(PieceColor::White, secs(5));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(57));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(60));
+}
+
+#[test]
+fn clock_delay() {
+    let mut clock = ChessClock::new(TimeControl::bronstein(secs(60), secs(3)));
+    // A quick move costs nothing, a slow one all but the delay
+    clock.tick(PieceColor::White, secs(2));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(60));
+    clock.tick(PieceColor::Black, secs(10));
+    clock.press(PieceColor::Black);
+    assert_eq!(clock.remaining(PieceColor::Black), secs(53));
+}
+
+#[test]
+fn clock_periods() {
+    let control: TimeControl = "2/100:1/50+5:30".parse().unwrap();
+    let mut clock = ChessClock::new(control);
+    for _ in 0..2 {
+        clock.tick(PieceColor::White, secs(10));
+        clock.press(PieceColor::White);
+    }
+    // The unused time carries over into the second period
+    assert_eq!(clock.remaining(PieceColor::White), secs(80 + 50));
+    clock.tick(PieceColor::White, secs(10));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(125 + 30));
+    // The last period repeats
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(155));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(100));
+}
+
+#[test]
+fn clock_flag() {
+    let mut clock = ChessClock::new(TimeControl::fischer(secs(10), secs(5)));
+    assert!(!clock.tick(PieceColor::White, secs(9)));
+    assert!(clock.tick(PieceColor::White, secs(2)));
+    assert_eq!(clock.flagged(), Some(PieceColor::White));
+    assert_eq!(clock.remaining(PieceColor::White), Duration::ZERO);
+    // Nothing moves after the flag fell
+    clock.press(PieceColor::White);
+    assert!(!clock.tick(PieceColor::Black, secs(1)));
+    assert_eq!(clock.remaining(PieceColor::White), Duration::ZERO);
+    assert_eq!(clock.remaining(PieceColor::Black), secs(10));
+}
+
+#[test]
+fn clock_restore() {
+    let mut record = GameRecord::new(Board::start());
+    for san in ["e4", "e5", "Nf3"] {
+        let board = record.board_at(record.moves.len());
+        let mv = board.parse_san(san).unwrap();
+        record.push(&board, mv);
+    }
+    record.moves[0].clock = Some(secs(55));
+    record.moves[1].clock = Some(secs(58));
+
+    let control: TimeControl = "2/60+1:30".parse().unwrap();
+    let mut clock = ChessClock::new(control);
+    clock.restore(&record);
+    // White made both moves of the first period, the third one has no
+    // `%clk` and keeps the time of the next period added on top
+    assert_eq!(clock.remaining(PieceColor::White), secs(55 + 1 + 30));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(58));
+}
+
+#[test]
+fn time_control_notation() {
+    for s in [
+        "300",
+        "300+2",
+        "300d2",
+        "40/7200:3600",
+        "40/5400+30:1800+30",
+    ] {
+        let control: TimeControl = s.parse().unwrap();
+        assert_eq!(control.to_string(), s);
+    }
+    assert_eq!(
+        "180+2".parse(),
+        Ok(TimeControl::fischer(secs(180), secs(2)))
+    );
+    assert_eq!(
+        "40/7200:3600".parse::<TimeControl>().unwrap().periods[0].moves,
+        Some(40)
+    );
+    for s in ["", "abc", "300+", "40/", "/300", "300:+2", "-5"] {
+        assert!(s.parse::<TimeControl>().is_err(), "{}", s);
+    }
+}
+
+fn record(fen: &str, sans: &[&str]) -> (GameRecord, Board) {
+    let mut record = GameRecord::new(board(fen));
+    let mut board = record.start.clone();
+    for san in sans {
+        let mv = board.parse_san(san).unwrap();
+        record.push(&board, mv);
+        board.make_move(mv);
+    }
+    (record, board)
+}
+
+#[test]
+fn threefold_repetition() {
+    let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+    let (twice, board) = record(START_FEN, &shuffle);
+    assert_eq!(twice.repetitions(), 2);
+    assert_eq!(twice.automatic_outcome(&board), None);
+    let (thrice, board) = record(START_FEN, &[shuffle, shuffle].concat());
+    assert_eq!(thrice.repetitions(), 3);
+    assert_eq!(
+        thrice.automatic_outcome(&board),
+        Some(Outcome::draw(Termination::ThreefoldRepetition))
+    );
+    // Back to the position after the first move
+    let (again, _) = record(START_FEN, &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3"]);
+    assert_eq!(again.repetitions(), 2);
+}
+
+#[test]
+fn repetition_key() {
+    let key = |fen: &str| RepetitionKey::new(&board(fen));
+    // Move counters don't matter
+    assert_eq!(
+        key("4k3/8/8/8/8/8/8/4K2R w K - 0 1"),
+        key("4k3/8/8/8/8/8/8/4K2R w K - 12 40")
+    );
+    // The side to move and castling rights do
+    assert_ne!(
+        key("4k3/8/8/8/8/8/8/4K2R w K - 0 1"),
+        key("4k3/8/8/8/8/8/8/4K2R b K - 0 1")
+    );
+    assert_ne!(
+        key("4k3/8/8/8/8/8/8/4K2R w K - 0 1"),
+        key("4k3/8/8/8/8/8/8/4K2R w - - 0 1")
+    );
+    // An en passant square only while the capture is possible
+    assert_eq!(
+        key("4k3/8/8/8/3P4/8/8/4K3 b - d3 0 1"),
+        key("4k3/8/8/8/3P4/8/8/4K3 b - - 0 1")
+    );
+    assert_ne!(
+        key("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1"),
+        key("4k3/8/8/8/3Pp3/8/8/4K3 b - - 0 1")
+    );
+}
+
+#[test]
+fn move_rules() {
+    let record = GameRecord::default();
+    let board = |halfmove: u32| board(&format!("4k3/8/8/8/8/8/8/R3K3 w - - {} 80", halfmove));
+    assert_eq!(record.claimable_draw(&board(99)), None);
+    assert_eq!(
+        record.claimable_draw(&board(100)),
+        Some(Outcome::draw(Termination::FiftyMoveRule))
+    );
+    assert_eq!(record.automatic_outcome(&board(100)), None);
+    assert_eq!(
+        record.automatic_outcome(&board(150)),
+        Some(Outcome::draw(Termination::SeventyFiveMoveRule))
+    );
+}
+
+#[test]
+fn insufficient_material() {
+    let insufficient = |fen: &str| board(fen).has_insufficient_material();
+    assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+    assert!(insufficient("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"));
+    assert!(insufficient("4k3/8/8/8/8/8/8/4KB2 w - - 0 1"));
+    // Bishops of both sides all on dark squares
+    assert!(insufficient("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+    assert!(!insufficient("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+    assert!(!insufficient("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1"));
+    assert!(!insufficient("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+
+    let mating = |fen: &str| board(fen).has_mating_material(PieceColor::White);
+    assert!(!mating("4k3/8/8/8/8/8/8/4KB2 w - - 0 1"));
+    // Two bishops on the same square color can't mate a lone king
+    assert!(!mating("4k3/8/8/8/8/8/8/B1B1K3 w - - 0 1"));
+    assert!(mating("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"));
+    assert!(mating("4k3/8/8/8/8/8/8/3NKB2 w - - 0 1"));
+    // A lone minor piece can with a piece of the opponent in the way
+    assert!(mating("4k3/4p3/8/8/8/8/8/4KB2 w - - 0 1"));
+}
+
+#[test]
+fn recorded_captures() {
+    let (record, _) = record("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", &["exd6", "Kf8", "d7"]);
+    let captured: Vec<_> = record.moves.iter().map(|m| m.captured.clone()).collect();
+    assert_eq!(
+        captured,
+        [vec![(PieceType::Pawn, PieceColor::Black)], vec![], vec![]]
+    );
+
+    // In Atomic the capturer and the pieces around go as well, pawns aside
+    let mut start = board("4k3/8/8/8/8/2nrp3/3P4/4K3 w - - 0 1");
+    start.variant = VariantKind::Atomic;
+    let mut record = GameRecord::new(start.clone());
+    record.push(&start, start.parse_san("dxe3").unwrap());
+    let captured = &record.moves[0].captured;
+    assert_eq!(captured.len(), 3);
+    for piece in [
+        (PieceType::Pawn, PieceColor::White),
+        (PieceType::Pawn, PieceColor::Black),
+        (PieceType::Rook, PieceColor::Black),
+    ] {
+        assert!(captured.contains(&piece));
+    }
+}
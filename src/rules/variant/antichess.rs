@@ -0,0 +1,48 @@
+use super::*;
+
+/// Losing chess: capturing is compulsory, the king is an ordinary piece and
+/// whoever runs out of pieces or moves wins.
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "Antichess"
+    }
+
+    fn setup(&self, board: &mut Board) {
+        board.castling = CastlingRights::default();
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for mv in board.pseudo_legal_moves() {
+            if mv.is_castle() {
+                continue;
+            }
+            moves.push(mv);
+            // Pawns may promote to a king as well
+            if mv.promotion() == Some(PieceType::Queen) {
+                let kind = match mv.kind {
+                    MoveKind::PromotionCapture(_) => MoveKind::PromotionCapture(PieceType::King),
+                    _ => MoveKind::Promotion(PieceType::King),
+                };
+                moves.push(Move::new(mv.from, mv.to, kind));
+            }
+        }
+        if moves.iter().any(Move::is_capture) {
+            moves.retain(Move::is_capture);
+        }
+        moves
+    }
+
+    fn decisive_outcome(&self, board: &Board) -> Option<Outcome> {
+        [board.side_to_move, board.side_to_move.opponent()]
+            .into_iter()
+            .find(|&color| !board.pieces().any(|(_, _, c)| c == color))
+            .map(|color| Outcome::win(color, Termination::NoPiecesLeft))
+    }
+
+    fn no_moves_outcome(&self, board: &Board) -> Outcome {
+        Outcome::win(board.side_to_move, Termination::Stalemate)
+    }
+}
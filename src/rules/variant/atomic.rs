@@ -0,0 +1,90 @@
+use super::*;
+
+/// Captures explode: the capturing piece and every piece but pawns next to
+/// the capture square leave the board too. Blowing up the enemy king wins.
+pub struct Atomic;
+
+/// Kings next to each other can't attack each other, since capturing would
+/// blow up both.
+fn kings_touch(board: &Board) -> bool {
+    match (
+        board.king_square(PieceColor::White),
+        board.king_square(PieceColor::Black),
+    ) {
+        (Some(white), Some(black)) => {
+            (white.file as i8 - black.file as i8).abs() <= 1
+                && (white.rank as i8 - black.rank as i8).abs() <= 1
+        }
+        _ => false,
+    }
+}
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "Atomic"
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let color = board.side_to_move;
+        board
+            .pseudo_legal_moves()
+            .into_iter()
+            .filter(|&mv| {
+                // A capturing king would blow itself up
+                if mv.is_capture() && matches!(board.get(mv.from), Some((PieceType::King, _))) {
+                    return false;
+                }
+                let mut next = board.clone();
+                next.make_move(mv);
+                match (next.king_square(color), next.king_square(color.opponent())) {
+                    (None, _) => false,
+                    (Some(_), None) => true,
+                    (Some(_), Some(_)) => kings_touch(&next) || !next.in_check(color),
+                }
+            })
+            .collect()
+    }
+
+    fn after_move(&self, board: &mut Board, mv: Move, captured: Option<(PieceType, PieceColor)>) {
+        if captured.is_none() {
+            return;
+        }
+        board.set(mv.to, None);
+        for df in -1..=1 {
+            for dr in -1..=1 {
+                if let Some(square) = board.offset(mv.to, df, dr) {
+                    if !matches!(board.get(square), Some((PieceType::Pawn, _))) {
+                        board.set(square, None);
+                    }
+                }
+            }
+        }
+
+        // Blown up kings and rooks take their castling rights with them
+        for color in [PieceColor::White, PieceColor::Black] {
+            let rank = board.back_rank(color);
+            let king_home = matches!(board.king_square(color), Some(king) if king.rank == rank);
+            for king_side in [true, false] {
+                let rook = Square::new(board.castling.rook_file(color, king_side), rank);
+                if !king_home || board.get(rook) != Some((PieceType::Rook, color)) {
+                    board.castling.set(color, king_side, false);
+                }
+            }
+        }
+    }
+
+    fn decisive_outcome(&self, board: &Board) -> Option<Outcome> {
+        [board.side_to_move, board.side_to_move.opponent()]
+            .into_iter()
+            .find(|&color| board.king_square(color).is_none())
+            .map(|color| Outcome::win(color.opponent(), Termination::KingExploded))
+    }
+
+    fn no_moves_outcome(&self, board: &Board) -> Outcome {
+        if board.in_check(board.side_to_move) && !kings_touch(board) {
+            Outcome::win(board.side_to_move.opponent(), Termination::Checkmate)
+        } else {
+            Outcome::draw(Termination::Stalemate)
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+/// Captured pieces change sides and go into the capturer's pocket, from
+/// where they can be dropped on any empty square instead of moving.
+pub struct Crazyhouse;
+
+fn bit(square: Square) -> u128 {
+    1 << square.index()
+}
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "Crazyhouse"
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let mut moves = board.king_safe_moves();
+        let color = board.side_to_move;
+        // Out of check a drop can't expose the king, in check it must block
+        let in_check = board.in_check(color);
+        for &ptype in DROP_PIECES.iter() {
+            if board.variant_state.pocket(color, ptype) == 0 {
+                continue;
+            }
+            for square in board.size().squares() {
+                let back_rank = square.rank == board.back_rank(PieceColor::White)
+                    || square.rank == board.back_rank(PieceColor::Black);
+                if board.get(square).is_some() || (ptype == PieceType::Pawn && back_rank) {
+                    continue;
+                }
+                let mv = Move::new(square, square, MoveKind::Drop(ptype));
+                if in_check {
+                    let mut next = board.clone();
+                    next.make_move(mv);
+                    if next.in_check(color) {
+                        continue;
+                    }
+                }
+                moves.push(mv);
+            }
+        }
+        moves
+    }
+
+    fn after_move(&self, board: &mut Board, mv: Move, captured: Option<(PieceType, PieceColor)>) {
+        let mover = board.side_to_move.opponent();
+        let state = &mut board.variant_state;
+        if let Some(ptype) = mv.dropped() {
+            let left = state.pocket(mover, ptype).saturating_sub(1);
+            state.set_pocket(mover, ptype, left);
+            return;
+        }
+
+        if let Some((ptype, _)) = captured {
+            let ptype = if state.promoted & bit(mv.to) != 0 {
+                PieceType::Pawn
+            } else {
+                ptype
+            };
+            let count = state.pocket(mover, ptype) + 1;
+            state.set_pocket(mover, ptype, count);
+        }
+        let was_promoted = state.promoted & bit(mv.from) != 0;
+        state.promoted &= !(bit(mv.from) | bit(mv.to));
+        if was_promoted || mv.promotion().is_some() {
+            state.promoted |= bit(mv.to);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+/// White plays 36 pawns and no king against a normal Black army. Black wins
+/// by taking every white piece, White by checkmate.
+pub struct Horde;
+
+impl Variant for Horde {
+    fn name(&self) -> &'static str {
+        "Horde"
+    }
+
+    /// The horde fills half of the classical board, with no room left on
+    /// smaller ones.
+    fn supports(&self, size: BoardSize) -> bool {
+        size == BoardSize::STANDARD
+    }
+
+    fn setup(&self, board: &mut Board) {
+        for square in board.size().squares().filter(|square| square.rank < 4) {
+            board.set(square, Some((PieceType::Pawn, PieceColor::White)));
+        }
+        for file in [1, 2, 5, 6] {
+            board.set(
+                Square::new(file, 4),
+                Some((PieceType::Pawn, PieceColor::White)),
+            );
+        }
+        board.castling.set(PieceColor::White, true, false);
+        board.castling.set(PieceColor::White, false, false);
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let mut moves = board.king_safe_moves();
+        if board.side_to_move != PieceColor::White {
+            return moves;
+        }
+        // Pawns on the first rank may advance two squares as well
+        for file in 0..board.size().files {
+            let from = Square::new(file, 0);
+            let (over, to) = (Square::new(file, 1), Square::new(file, 2));
+            if board.get(from) == Some((PieceType::Pawn, PieceColor::White))
+                && board.get(over).is_none()
+                && board.get(to).is_none()
+            {
+                let mv = Move::new(from, to, MoveKind::Quiet);
+                let mut next = board.clone();
+                next.make_move(mv);
+                if !next.in_check(PieceColor::White) {
+                    moves.push(mv);
+                }
+            }
+        }
+        moves
+    }
+
+    fn decisive_outcome(&self, board: &Board) -> Option<Outcome> {
+        if board
+            .pieces()
+            .any(|(_, _, color)| color == PieceColor::White)
+        {
+            None
+        } else {
+            Some(Outcome::win(PieceColor::Black, Termination::NoPiecesLeft))
+        }
+    }
+}
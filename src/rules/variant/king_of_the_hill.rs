@@ -0,0 +1,24 @@
+use super::*;
+
+/// Whether `square` is in the centre of `size`: d4, e4, d5 and e5 on 8x8,
+/// the middle file or rank alone where there is an odd number of them.
+fn on_hill(size: BoardSize, square: Square) -> bool {
+    let middle = |n: u8, at: u8| (n - 1) / 2 <= at && at <= n / 2;
+    middle(size.files, square.file) && middle(size.ranks, square.rank)
+}
+
+/// Bringing the king to one of the centre squares also wins.
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "King of the Hill"
+    }
+
+    fn decisive_outcome(&self, board: &Board) -> Option<Outcome> {
+        [PieceColor::White, PieceColor::Black]
+            .into_iter()
+            .find(|&color| matches!(board.king_square(color), Some(king) if on_hill(board.size(), king)))
+            .map(|color| Outcome::win(color, Termination::KingOfTheHill))
+    }
+}
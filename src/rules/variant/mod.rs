@@ -0,0 +1,191 @@
+//! Alternative rule sets. Every `Board` names the `VariantKind` it is played
+//! under, and the board dispatches move generation, move side effects and
+//! game endings to that variant's `Variant` implementation.
+
+use super::*;
+
+mod antichess;
+mod atomic;
+mod crazyhouse;
+mod horde;
+mod king_of_the_hill;
+mod three_check;
+
+pub use antichess::Antichess;
+pub use atomic::Atomic;
+pub use crazyhouse::Crazyhouse;
+pub use horde::Horde;
+pub use king_of_the_hill::KingOfTheHill;
+pub use three_check::ThreeCheck;
+
+/// The rules of one variant. Defaults are those of standard chess.
+pub trait Variant: Sync {
+    /// Name as written in the PGN Variant tag.
+    fn name(&self) -> &'static str;
+
+    /// Whether the variant can be played on a board of `size`.
+    fn supports(&self, _size: BoardSize) -> bool {
+        true
+    }
+
+    /// Turns the standard starting position `board`, classical or Chess960,
+    /// into the one of the variant. Only called on sizes it `supports`.
+    fn setup(&self, _board: &mut Board) {}
+
+    /// All moves the side to move may play.
+    fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        board.king_safe_moves()
+    }
+
+    /// Called by `Board::make_move` once `mv` is on the board and the side
+    /// to move has changed, with the piece it captured.
+    fn after_move(
+        &self,
+        _board: &mut Board,
+        _mv: Move,
+        _captured: Option<(PieceType, PieceColor)>,
+    ) {
+    }
+
+    /// A result the variant decides before looking at the moves left, like
+    /// a king reaching the centre.
+    fn decisive_outcome(&self, _board: &Board) -> Option<Outcome> {
+        None
+    }
+
+    /// The result when the side to move has no legal moves.
+    fn no_moves_outcome(&self, board: &Board) -> Outcome {
+        if board.in_check(board.side_to_move) {
+            Outcome::win(board.side_to_move.opponent(), Termination::Checkmate)
+        } else {
+            Outcome::draw(Termination::Stalemate)
+        }
+    }
+
+    /// Whether neither side can win any more. Most variants have other ways
+    /// to win than checkmate, so only standard chess has dead positions.
+    fn insufficient_material(&self, _board: &Board) -> bool {
+        false
+    }
+}
+
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+
+    fn insufficient_material(&self, board: &Board) -> bool {
+        board.has_insufficient_material()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariantKind {
+    Standard,
+    KingOfTheHill,
+    ThreeCheck,
+    Atomic,
+    Horde,
+    Antichess,
+    Crazyhouse,
+}
+
+impl VariantKind {
+    pub const ALL: [VariantKind; 7] = [
+        VariantKind::Standard,
+        VariantKind::KingOfTheHill,
+        VariantKind::ThreeCheck,
+        VariantKind::Atomic,
+        VariantKind::Horde,
+        VariantKind::Antichess,
+        VariantKind::Crazyhouse,
+    ];
+
+    pub fn rules(self) -> &'static dyn Variant {
+        match self {
+            VariantKind::Standard => &Standard,
+            VariantKind::KingOfTheHill => &KingOfTheHill,
+            VariantKind::ThreeCheck => &ThreeCheck,
+            VariantKind::Atomic => &Atomic,
+            VariantKind::Horde => &Horde,
+            VariantKind::Antichess => &Antichess,
+            VariantKind::Crazyhouse => &Crazyhouse,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.rules().name()
+    }
+
+    /// Parses a variant name as in the PGN Variant tag or a short form like
+    /// `koth`, ignoring case, spaces and dashes.
+    pub fn parse(s: &str) -> Option<VariantKind> {
+        let name: String = s
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let kind = match name.as_str() {
+            "standard" | "chess" => VariantKind::Standard,
+            "kingofthehill" | "koth" => VariantKind::KingOfTheHill,
+            "threecheck" | "3check" => VariantKind::ThreeCheck,
+            "atomic" => VariantKind::Atomic,
+            "horde" => VariantKind::Horde,
+            "antichess" | "giveaway" | "suicide" => VariantKind::Antichess,
+            "crazyhouse" | "zh" => VariantKind::Crazyhouse,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// `start` set up for this variant, `None` if the variant can't be
+    /// played on a board of its size.
+    pub fn setup(self, start: Board) -> Option<Board> {
+        if !self.rules().supports(start.size()) {
+            return None;
+        }
+        let mut board = start;
+        board.variant = self;
+        self.rules().setup(&mut board);
+        Some(board)
+    }
+}
+
+/// Pieces that can be dropped, in the order of `VariantState::pockets`.
+pub const DROP_PIECES: [PieceType; 5] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+/// Variant bookkeeping beyond the pieces on the board. Per color arrays are
+/// indexed by `PieceColor as usize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VariantState {
+    /// Checks given by each side, for Three-check.
+    pub checks: [u8; 2],
+    /// Pieces in hand for Crazyhouse, counted in the order of `DROP_PIECES`.
+    pub pockets: [[u8; 5]; 2],
+    /// Crazyhouse squares holding promoted pieces, as bits `Square::index`.
+    /// Captured, they go into the pocket as pawns.
+    pub promoted: u128,
+}
+
+impl VariantState {
+    pub fn pocket(&self, color: PieceColor, ptype: PieceType) -> u8 {
+        match DROP_PIECES.iter().position(|&p| p == ptype) {
+            Some(i) => self.pockets[color as usize][i],
+            None => 0,
+        }
+    }
+
+    pub fn set_pocket(&mut self, color: PieceColor, ptype: PieceType, count: u8) {
+        if let Some(i) = DROP_PIECES.iter().position(|&p| p == ptype) {
+            self.pockets[color as usize][i] = count;
+        }
+    }
+}
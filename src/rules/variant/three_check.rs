@@ -0,0 +1,26 @@
+use super::*;
+
+const CHECKS_TO_WIN: u8 = 3;
+
+/// Checking the opponent's king for the third time also wins.
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "Three-check"
+    }
+
+    fn after_move(&self, board: &mut Board, _mv: Move, _captured: Option<(PieceType, PieceColor)>) {
+        if board.in_check(board.side_to_move) {
+            let mover = board.side_to_move.opponent();
+            board.variant_state.checks[mover as usize] += 1;
+        }
+    }
+
+    fn decisive_outcome(&self, board: &Board) -> Option<Outcome> {
+        [PieceColor::White, PieceColor::Black]
+            .into_iter()
+            .find(|&color| board.variant_state.checks[color as usize] >= CHECKS_TO_WIN)
+            .map(|color| Outcome::win(color, Termination::ThreeChecks))
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::board::MakeMove;
+use crate::history::History;
+use crate::rules::{ChessClock, GameRecord, GameResult, RecordedMove};
+
+const SAVE_DIR: &str = "games";
+
+pub fn init(app: &mut App) {
+    app.add_system(save_game).add_system(replay_moves);
+}
+
+/// Moves of a loaded game that are still to be played back on the board.
+pub struct Replay {
+    /// The loaded moves, kept for the clock times once the replay is done.
+    moves: Vec<RecordedMove>,
+    next: usize,
+    result: GameResult,
+    timer: Timer,
+}
+
+impl Replay {
+    pub fn new(record: &GameRecord) -> Self {
+        Replay {
+            moves: record.moves.clone(),
+            next: 0,
+            result: record.result,
+            timer: Timer::from_seconds(1.0, true),
+        }
+    }
+}
+
+/// Reads a PGN file. Returns the record to start the app with, which keeps
+/// the tags and starting position but no moves, and the replay of the moves.
+pub fn load(path: &Path) -> Result<(GameRecord, Replay), String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let loaded = GameRecord::from_pgn(&text).map_err(|err| err.to_string())?;
+    let replay = Replay::new(&loaded);
+
+    let mut record = loaded;
+    record.moves.clear();
+    record.result = GameResult::Ongoing;
+    Ok((record, replay))
+}
+
+/// Writes `record` to a new file in the save directory.
+pub fn save(record: &GameRecord) -> io::Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut record = record.clone();
+    if record.tag("Date") == Some("????.??.??") {
+        record.set_tag("Date", &pgn_date(secs));
+    }
+
+    fs::create_dir_all(SAVE_DIR)?;
+    let path = Path::new(SAVE_DIR).join(format!("{}.pgn", secs));
+    fs::write(&path, record.to_pgn())?;
+    Ok(path)
+}
+
+/// Save the game so far with F5.
+/// Saves the whole game, also while an earlier position is shown.
+fn save_game(input: Res<Input<KeyCode>>, record: Res<GameRecord>, history: Res<History>) {
+    if input.just_pressed(KeyCode::F5) {
+        match save(&history.whole_game(&record)) {
+            Ok(path) => info!("saved game to {}", path.display()),
+            Err(err) => error!("failed to save game: {}", err),
+        }
+    }
+}
+
+fn replay_moves(
+    mut commands: Commands,
+    time: Res<Time>,
+    replay: Option<ResMut<Replay>>,
+    clock: Option<ResMut<ChessClock>>,
+    mut record: ResMut<GameRecord>,
+    mut make_move: EventWriter<MakeMove>,
+) {
+    let mut replay = match replay {
+        Some(replay) => replay,
+        None => return,
+    };
+    if !replay.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if let Some(recorded) = replay.moves.get(replay.next) {
+        make_move.send(MakeMove(recorded.mv));
+        replay.next += 1;
+        return;
+    }
+
+    // Results like resignations can't be derived from the moves
+    record.result = replay.result;
+    for (recorded, loaded) in record.moves.iter_mut().zip(&replay.moves) {
+        recorded.clock = loaded.clock;
+    }
+    if let Some(mut clock) = clock {
+        clock.restore(&record);
+    }
+    commands.remove_resource::<Replay>();
+}
+
+/// `YYYY.MM.DD` for a UNIX timestamp, as used by the PGN Date tag.
+fn pgn_date(secs: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,242 @@
+use std::{fs, io};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+use crate::highlight::SquareMaterials;
+use crate::piece::{tinted, PieceAssets, PieceColor, PieceType};
+
+const THEMES_FILE: &str = "assets/themes.toml";
+const SETTINGS_FILE: &str = "settings.toml";
+const PALETTE: &str = "palette/resurrect-64-1x.png";
+
+pub fn init(app: &mut App) {
+    let themes = Themes::load().unwrap_or_else(|err| {
+        eprintln!("invalid themes file '{}': {}", THEMES_FILE, err);
+        std::process::exit(1);
+    });
+    app.insert_resource(themes)
+        .insert_resource(ThemeSettings::load())
+        .add_startup_system(load_palette)
+        .add_system(settings_menu)
+        .add_system(apply_themes);
+}
+
+/// A colour given as `[r, g, b]` or as an index into the palette image.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum ThemeColor {
+    Rgb([f32; 3]),
+    Palette(usize),
+}
+
+impl ThemeColor {
+    /// `None` while the palette has not loaded, or for an index past it.
+    fn resolve(self, palette: &[Color]) -> Option<Color> {
+        match self {
+            ThemeColor::Rgb([r, g, b]) => Some(Color::rgb(r, g, b)),
+            ThemeColor::Palette(index) => palette.get(index).copied(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BoardTheme {
+    pub name: String,
+    pub light: ThemeColor,
+    pub dark: ThemeColor,
+    /// Images under `assets` the square colours are multiplied with.
+    pub light_texture: Option<String>,
+    pub dark_texture: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PieceTheme {
+    pub name: String,
+    /// The `.pieces.toml` piece set, see `piece_set`.
+    pub set: String,
+    pub white: ThemeColor,
+    pub black: ThemeColor,
+}
+
+/// The themes of `assets/themes.toml` to pick from.
+#[derive(Debug, Deserialize)]
+pub struct Themes {
+    pub board: Vec<BoardTheme>,
+    pub pieces: Vec<PieceTheme>,
+}
+
+impl Themes {
+    fn load() -> Result<Themes, String> {
+        let text = fs::read_to_string(THEMES_FILE).map_err(|err| err.to_string())?;
+        let themes: Themes = toml::from_str(&text).map_err(|err| err.to_string())?;
+        if themes.board.is_empty() || themes.pieces.is_empty() {
+            return Err("needs at least one board and one pieces theme".to_string());
+        }
+        Ok(themes)
+    }
+
+    /// The board theme called `name`, the first one if there is none.
+    pub fn board(&self, name: &str) -> &BoardTheme {
+        self.board
+            .iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or(&self.board[0])
+    }
+
+    /// The pieces theme called `name`, the first one if there is none.
+    pub fn pieces(&self, name: &str) -> &PieceTheme {
+        self.pieces
+            .iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or(&self.pieces[0])
+    }
+}
+
+/// Names of the chosen themes, kept in `settings.toml` between sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeSettings {
+    pub board: String,
+    pub pieces: String,
+}
+
+impl ThemeSettings {
+    /// The saved settings, or the first themes if there are none yet.
+    fn load() -> Self {
+        fs::read_to_string(SETTINGS_FILE)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let text =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        fs::write(SETTINGS_FILE, text)
+    }
+}
+
+/// The palette image theme colours can refer to.
+struct Palette(Handle<Image>);
+
+fn load_palette(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Palette(asset_server.load(PALETTE)));
+}
+
+/// Colours of the palette image, pixel by pixel.
+fn palette_colors(image: &Image) -> Vec<Color> {
+    image
+        .data
+        .chunks_exact(4)
+        .map(|pixel| Color::rgba_u8(pixel[0], pixel[1], pixel[2], pixel[3]))
+        .collect()
+}
+
+/// The settings window, collapsed until opened, with a choice of board and
+/// pieces themes. Changes are saved right away.
+fn settings_menu(
+    mut egui_context: ResMut<EguiContext>,
+    themes: Res<Themes>,
+    mut settings: ResMut<ThemeSettings>,
+) {
+    let mut chosen = settings.clone();
+    egui::Window::new("Settings")
+        .default_open(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("Board")
+                .selected_text(themes.board(&chosen.board).name.as_str())
+                .show_ui(ui, |ui| {
+                    for theme in &themes.board {
+                        let name = theme.name.as_str();
+                        ui.selectable_value(&mut chosen.board, name.to_string(), name);
+                    }
+                });
+            egui::ComboBox::from_label("Pieces")
+                .selected_text(themes.pieces(&chosen.pieces).name.as_str())
+                .show_ui(ui, |ui| {
+                    for theme in &themes.pieces {
+                        let name = theme.name.as_str();
+                        ui.selectable_value(&mut chosen.pieces, name.to_string(), name);
+                    }
+                });
+        });
+
+    if chosen != *settings {
+        *settings = chosen;
+        if let Err(err) = settings.save() {
+            error!("failed to save settings: {}", err);
+        }
+    }
+}
+
+/// Carries the chosen themes over to the square and piece materials and
+/// switches the piece set. Waits for the palette if a colour comes from it.
+fn apply_themes(
+    settings: Res<ThemeSettings>,
+    themes: Res<Themes>,
+    palette: Res<Palette>,
+    images: Res<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    square_materials: Res<SquareMaterials>,
+    mut piece_assets: ResMut<PieceAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pending: Local<bool>,
+) {
+    if settings.is_changed() {
+        *pending = true;
+    }
+    if !*pending {
+        return;
+    }
+    let palette = images
+        .get(&palette.0)
+        .map(palette_colors)
+        .unwrap_or_default();
+    let board = themes.board(&settings.board);
+    let pieces = themes.pieces(&settings.pieces);
+    let (light, dark, white, black) = match [board.light, board.dark, pieces.white, pieces.black]
+        .map(|color| color.resolve(&palette))
+    {
+        [Some(light), Some(dark), Some(white), Some(black)] => (light, dark, white, black),
+        _ => return,
+    };
+    *pending = false;
+
+    for (handle, color, texture) in [
+        (&square_materials.light, light, &board.light_texture),
+        (&square_materials.dark, dark, &board.dark_texture),
+    ] {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = color;
+            material.base_color_texture = texture
+                .as_ref()
+                .map(|path| asset_server.load(path.as_str()));
+        }
+    }
+
+    for (color, base) in [(PieceColor::White, white), (PieceColor::Black, black)] {
+        if let Some(material) = materials.get_mut(piece_assets.material(PieceType::Pawn, color)) {
+            material.base_color = base;
+        }
+    }
+    for (&(ptype, color), handle) in &piece_assets.fairy_materials {
+        let base = match color {
+            PieceColor::White => white,
+            PieceColor::Black => black,
+        };
+        let tint = piece_assets
+            .fairy_looks
+            .get(&ptype)
+            .and_then(|look| look.tint);
+        if let (Some(material), Some(tint)) = (materials.get_mut(handle), tint) {
+            material.base_color = tinted(base, tint);
+        }
+    }
+
+    let set = asset_server.load(pieces.set.as_str());
+    if piece_assets.set != set {
+        piece_assets.set = set;
+    }
+}
@@ -0,0 +1,393 @@
+//! Parsing and formatting of the Universal Chess Interface protocol, shared
+//! by the bridge to external engines and the `uci` binary.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::engine::{SearchInfo, SearchLimits};
+use crate::rules::{Board, Move, PieceColor, VariantKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Moves until mate, negative if the engine is getting mated.
+    Mate(i32),
+}
+
+/// The fields of an `info` line that we care about.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    /// Milliseconds searched.
+    pub time: Option<u64>,
+    pub pv: Vec<String>,
+    pub string: Option<String>,
+}
+
+impl From<&SearchInfo> for Info {
+    fn from(info: &SearchInfo) -> Self {
+        let millis = info.time.as_millis() as u64;
+        Info {
+            depth: Some(info.depth),
+            seldepth: None,
+            score: Some(match info.mate_in() {
+                Some(moves) => Score::Mate(moves),
+                None => Score::Centipawns(info.score),
+            }),
+            nodes: Some(info.nodes),
+            nps: Some(info.nodes * 1000 / millis.max(1)),
+            time: Some(millis),
+            pv: info.pv.iter().map(Move::to_string).collect(),
+            string: None,
+        }
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "info")?;
+        if let Some(depth) = self.depth {
+            write!(f, " depth {}", depth)?;
+        }
+        if let Some(seldepth) = self.seldepth {
+            write!(f, " seldepth {}", seldepth)?;
+        }
+        match self.score {
+            Some(Score::Centipawns(cp)) => write!(f, " score cp {}", cp)?,
+            Some(Score::Mate(moves)) => write!(f, " score mate {}", moves)?,
+            None => {}
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {}", nodes)?;
+        }
+        if let Some(nps) = self.nps {
+            write!(f, " nps {}", nps)?;
+        }
+        if let Some(time) = self.time {
+            write!(f, " time {}", time)?;
+        }
+        if !self.pv.is_empty() {
+            write!(f, " pv {}", self.pv.join(" "))?;
+        }
+        if let Some(string) = &self.string {
+            write!(f, " string {}", string)?;
+        }
+        Ok(())
+    }
+}
+
+/// A line sent from an engine to the GUI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineMessage {
+    Id {
+        name: String,
+        value: String,
+    },
+    UciOk,
+    ReadyOk,
+    BestMove {
+        mv: String,
+        ponder: Option<String>,
+    },
+    Info(Info),
+    /// `option name <name> type <type> ...`, with the `var` values of a
+    /// combo option. Only `UCI_Variant` is looked at.
+    Option {
+        name: String,
+        vars: Vec<String>,
+    },
+    /// Anything else, which we don't use.
+    Other(String),
+}
+
+impl EngineMessage {
+    pub fn parse(line: &str) -> EngineMessage {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("id") => {
+                let name = tokens.next().unwrap_or_default().to_string();
+                let value = tokens.collect::<Vec<_>>().join(" ");
+                EngineMessage::Id { name, value }
+            }
+            Some("uciok") => EngineMessage::UciOk,
+            Some("readyok") => EngineMessage::ReadyOk,
+            Some("bestmove") => match tokens.next() {
+                Some(mv) => {
+                    let ponder = match tokens.next() {
+                        Some("ponder") => tokens.next().map(str::to_string),
+                        _ => None,
+                    };
+                    EngineMessage::BestMove {
+                        mv: mv.to_string(),
+                        ponder,
+                    }
+                }
+                None => EngineMessage::Other(line.to_string()),
+            },
+            Some("info") => EngineMessage::Info(parse_info(tokens)),
+            Some("option") => {
+                parse_option(tokens).unwrap_or_else(|| EngineMessage::Other(line.to_string()))
+            }
+            _ => EngineMessage::Other(line.to_string()),
+        }
+    }
+}
+
+fn parse_option<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<EngineMessage> {
+    if tokens.next() != Some("name") {
+        return None;
+    }
+    let name: Vec<&str> = tokens.by_ref().take_while(|&t| t != "type").collect();
+    let mut vars = Vec::new();
+    while let Some(token) = tokens.next() {
+        if token == "var" {
+            vars.extend(tokens.next().map(str::to_string));
+        }
+    }
+    Some(EngineMessage::Option {
+        name: name.join(" "),
+        vars,
+    })
+}
+
+fn parse_info<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Info {
+    let mut info = Info::default();
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => info.depth = tokens.next().and_then(|t| t.parse().ok()),
+            "seldepth" => info.seldepth = tokens.next().and_then(|t| t.parse().ok()),
+            "nodes" => info.nodes = tokens.next().and_then(|t| t.parse().ok()),
+            "nps" => info.nps = tokens.next().and_then(|t| t.parse().ok()),
+            "time" => info.time = tokens.next().and_then(|t| t.parse().ok()),
+            "score" => {
+                let kind = tokens.next();
+                let value = tokens.next().and_then(|t| t.parse().ok());
+                info.score = match (kind, value) {
+                    (Some("cp"), Some(value)) => Some(Score::Centipawns(value)),
+                    (Some("mate"), Some(value)) => Some(Score::Mate(value)),
+                    _ => None,
+                };
+            }
+            // Both run to the end of the line
+            "pv" => {
+                info.pv = tokens.by_ref().map(str::to_string).collect();
+            }
+            "string" => {
+                info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" "));
+            }
+            _ => {}
+        }
+    }
+    info
+}
+
+/// `mv` in long algebraic notation, e.g. `e7e8q`. In Chess960 castling is
+/// written as the king taking its own rook, as with `UCI_Chess960`.
+pub fn move_text(board: &Board, mv: Move) -> String {
+    if board.chess960 && mv.is_castle() {
+        let (rook, _) = board.castling_rook_squares(mv);
+        format!("{}{}", mv.from, rook)
+    } else {
+        mv.to_string()
+    }
+}
+
+/// Finds the legal move written in long algebraic notation. Castling is
+/// also understood in the notation of the other mode when that is not
+/// ambiguous.
+pub fn parse_move(board: &Board, text: &str) -> Option<Move> {
+    let moves = board.legal_moves();
+    moves
+        .iter()
+        .find(|&&mv| move_text(board, mv) == text)
+        .or_else(|| {
+            let mut other_mode = board.clone();
+            other_mode.chess960 = !board.chess960;
+            let mut matching = moves
+                .iter()
+                .filter(|&&mv| mv.is_castle() && move_text(&other_mode, mv) == text);
+            matching.next().filter(|_| matching.next().is_none())
+        })
+        .copied()
+}
+
+/// What engines that play variants, like Fairy-Stockfish, call `kind` in
+/// the `UCI_Variant` option.
+pub fn variant_name(kind: VariantKind) -> &'static str {
+    match kind {
+        VariantKind::Standard => "chess",
+        VariantKind::KingOfTheHill => "kingofthehill",
+        VariantKind::ThreeCheck => "3check",
+        VariantKind::Atomic => "atomic",
+        VariantKind::Horde => "horde",
+        VariantKind::Antichess => "antichess",
+        VariantKind::Crazyhouse => "crazyhouse",
+    }
+}
+
+/// `position` command reaching `board` by playing `moves` from `start`.
+pub fn position_command(start: &Board, moves: &[Move]) -> String {
+    let mut command = if *start == Board::start() {
+        "position startpos".to_string()
+    } else {
+        format!("position fen {}", start.to_fen())
+    };
+    if !moves.is_empty() {
+        command.push_str(" moves");
+        let mut board = start.clone();
+        for &mv in moves {
+            command.push(' ');
+            command.push_str(&move_text(&board, mv));
+            board.make_move(mv);
+        }
+    }
+    command
+}
+
+/// Arguments of the `go` command. Times are in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoParams {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    /// Search limits for `side`, budgeting a share of its remaining clock
+    /// time when no fixed move time is given.
+    pub fn limits(&self, side: PieceColor) -> SearchLimits {
+        let (time, increment) = match side {
+            PieceColor::White => (self.wtime, self.winc.unwrap_or(0)),
+            PieceColor::Black => (self.btime, self.binc.unwrap_or(0)),
+        };
+        let movetime = if self.infinite {
+            None
+        } else if let Some(movetime) = self.movetime {
+            Some(movetime)
+        } else {
+            time.map(|time| {
+                let moves_left = self.movestogo.unwrap_or(30).max(1) as u64;
+                (time / moves_left + increment / 2).min(time / 2).max(1)
+            })
+        };
+        SearchLimits {
+            depth: self.depth,
+            movetime: movetime.map(Duration::from_millis),
+            nodes: self.nodes,
+        }
+    }
+}
+
+/// A line sent from the GUI to an engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuiCommand {
+    Uci,
+    Debug(bool),
+    IsReady,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    UciNewGame,
+    /// The position to search, as a start position and the moves from it.
+    Position {
+        start: Box<Board>,
+        moves: Vec<Move>,
+    },
+    Go(GoParams),
+    Stop,
+    PonderHit,
+    Quit,
+}
+
+impl GuiCommand {
+    /// Parses a command. Unknown commands and malformed positions are
+    /// reported as errors, which engines should ignore.
+    pub fn parse(line: &str) -> Result<GuiCommand, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let rest = tokens.get(1..).unwrap_or_default();
+        match tokens.first().copied() {
+            Some("uci") => Ok(GuiCommand::Uci),
+            Some("debug") => Ok(GuiCommand::Debug(rest.first() == Some(&"on"))),
+            Some("isready") => Ok(GuiCommand::IsReady),
+            Some("setoption") => parse_setoption(rest),
+            Some("ucinewgame") => Ok(GuiCommand::UciNewGame),
+            Some("position") => parse_position(rest),
+            Some("go") => Ok(GuiCommand::Go(parse_go(rest))),
+            Some("stop") => Ok(GuiCommand::Stop),
+            Some("ponderhit") => Ok(GuiCommand::PonderHit),
+            Some("quit") => Ok(GuiCommand::Quit),
+            _ => Err(format!("unknown command '{}'", line.trim())),
+        }
+    }
+}
+
+fn parse_setoption(tokens: &[&str]) -> Result<GuiCommand, String> {
+    let tokens = match tokens.split_first() {
+        Some((&"name", rest)) => rest,
+        _ => return Err("setoption needs a name first".to_string()),
+    };
+    let value_at = tokens.iter().position(|&t| t == "value");
+    let name = tokens[..value_at.unwrap_or(tokens.len())].join(" ");
+    let value = value_at.map(|i| tokens[i + 1..].join(" "));
+    Ok(GuiCommand::SetOption { name, value })
+}
+
+fn parse_position(tokens: &[&str]) -> Result<GuiCommand, String> {
+    let moves_at = tokens.iter().position(|&t| t == "moves");
+    let setup = &tokens[..moves_at.unwrap_or(tokens.len())];
+    let start = match setup.split_first() {
+        Some((&"startpos", _)) => Board::start(),
+        Some((&"fen", fen)) => Board::from_fen(&fen.join(" ")).map_err(|err| err.to_string())?,
+        _ => return Err("position needs startpos or fen".to_string()),
+    };
+
+    let mut board = start.clone();
+    let mut moves = Vec::new();
+    for &text in moves_at.map_or(&[][..], |i| &tokens[i + 1..]) {
+        let mv = parse_move(&board, text).ok_or_else(|| format!("illegal move '{}'", text))?;
+        board.make_move(mv);
+        moves.push(mv);
+    }
+    Ok(GuiCommand::Position {
+        start: Box::new(start),
+        moves,
+    })
+}
+
+/// Some GUIs send a negative time once a flag has fallen, which leaves no
+/// time at all.
+fn millis(n: i64) -> u64 {
+    n.max(0) as u64
+}
+
+fn parse_go(tokens: &[&str]) -> GoParams {
+    let mut params = GoParams::default();
+    let mut tokens = tokens.iter();
+    while let Some(&token) = tokens.next() {
+        let mut number = || tokens.next().and_then(|t| t.parse::<i64>().ok());
+        match token {
+            "depth" => params.depth = number().and_then(|n| n.try_into().ok()),
+            "nodes" => params.nodes = number().and_then(|n| n.try_into().ok()),
+            "movetime" => params.movetime = number().map(millis),
+            "wtime" => params.wtime = number().map(millis),
+            "btime" => params.btime = number().map(millis),
+            "winc" => params.winc = number().map(millis),
+            "binc" => params.binc = number().map(millis),
+            "movestogo" => params.movestogo = number().and_then(|n| n.try_into().ok()),
+            "infinite" => params.infinite = true,
+            _ => {}
+        }
+    }
+    params
+}
@@ -0,0 +1,289 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{
+    mpsc::{self, Receiver, TryRecvError},
+    Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::board::{BoardSystem, MakeMove};
+use crate::history::History;
+use crate::outcome::{Action, GameAction};
+use crate::player::{PlayerKind, Players};
+use crate::rules::{
+    Board, BoardSize, ChessClock, GameRecord, GameResult, Move, PieceColor, PieceType, VariantKind,
+};
+use crate::save::Replay;
+use crate::uci::{self, EngineMessage, Info};
+
+pub fn init(app: &mut App) {
+    app.add_system(drive_engine.before(BoardSystem::ApplyMoves));
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EngineState {
+    /// Waiting for `uciok` and `readyok`.
+    Starting,
+    Ready,
+    Thinking {
+        board: Board,
+    },
+    /// Told to stop a search that is no longer wanted, its `bestmove` is
+    /// ignored.
+    Stopping,
+    /// Played an illegal move or can't play this game, so it is not asked
+    /// for moves again.
+    Failed,
+    Exited,
+}
+
+/// An external engine process playing the `PlayerKind::External` sides.
+///
+/// Output is read on a separate thread so the frame loop never blocks on the
+/// engine; the lines are picked up each frame by `drive_engine`.
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Mutex<Receiver<String>>,
+    state: EngineState,
+    /// Whether `UCI_Chess960` was switched on.
+    chess960: bool,
+    /// Values the engine offers for `UCI_Variant`, none if it only plays
+    /// standard chess.
+    variants: Vec<String>,
+    /// The `UCI_Variant` switched to.
+    variant: VariantKind,
+    pub movetime: Duration,
+    /// Name reported by the engine with `id name`.
+    pub name: Option<String>,
+    /// The latest `info` line of the current or last search.
+    pub last_info: Option<Info>,
+}
+
+impl UciEngine {
+    /// Starts the engine at `path` and begins the UCI handshake.
+    pub fn spawn(path: &str, movetime: Duration) -> io::Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            child,
+            stdin,
+            lines: Mutex::new(receiver),
+            state: EngineState::Starting,
+            chess960: false,
+            variants: Vec::new(),
+            variant: VariantKind::Standard,
+            movetime,
+            name: None,
+            last_info: None,
+        };
+        engine.send("uci");
+        Ok(engine)
+    }
+
+    fn send(&mut self, command: &str) {
+        debug!("uci > {}", command);
+        if let Err(err) = writeln!(self.stdin, "{}", command).and_then(|_| self.stdin.flush()) {
+            error!("failed to write to engine: {}", err);
+            self.state = EngineState::Exited;
+        }
+    }
+
+    /// Switches the engine to the rules of the game starting at `start`, or
+    /// says why it can't play it.
+    fn prepare(&mut self, start: &Board) -> Result<(), String> {
+        let fairy = start
+            .pieces()
+            .any(|(_, ptype, _)| matches!(ptype, PieceType::Fairy(_)));
+        if start.size() != BoardSize::STANDARD || fairy {
+            return Err("UCI engines only play on 8x8 boards without fairy pieces".to_string());
+        }
+        if start.chess960 && !self.chess960 {
+            self.send("setoption name UCI_Chess960 value true");
+            self.chess960 = true;
+        }
+        if start.variant != self.variant {
+            let name = uci::variant_name(start.variant);
+            if !self.variants.iter().any(|offered| offered == name) {
+                return Err(format!("the engine doesn't play {}", start.variant.name()));
+            }
+            self.send(&format!("setoption name UCI_Variant value {}", name));
+            self.variant = start.variant;
+        }
+        Ok(())
+    }
+
+    /// Stops asking the engine for moves and resigns for it, so the game
+    /// doesn't wait for a move that never comes.
+    fn give_up(&mut self, reason: &str, color: PieceColor, actions: &mut EventWriter<GameAction>) {
+        error!("{}, resigning for {:?}", reason, color);
+        self.state = EngineState::Failed;
+        actions.send(GameAction {
+            color,
+            action: Action::Resign,
+        });
+    }
+
+    fn handle(
+        &mut self,
+        message: EngineMessage,
+        board: &Board,
+        make_move: &mut EventWriter<MakeMove>,
+        actions: &mut EventWriter<GameAction>,
+    ) {
+        match message {
+            EngineMessage::Id { name, value } if name == "name" => self.name = Some(value),
+            EngineMessage::Option { name, vars } if name == "UCI_Variant" => self.variants = vars,
+            EngineMessage::UciOk => self.send("isready"),
+            EngineMessage::ReadyOk if self.state == EngineState::Starting => {
+                self.send("ucinewgame");
+                self.state = EngineState::Ready;
+            }
+            EngineMessage::Info(info) => self.last_info = Some(info),
+            EngineMessage::BestMove { mv, .. } => {
+                match std::mem::replace(&mut self.state, EngineState::Ready) {
+                    EngineState::Thinking { board: searched } if searched == *board => {
+                        match uci::parse_move(board, &mv) {
+                            Some(mv) => make_move.send(MakeMove(mv)),
+                            None => self.give_up(
+                                &format!("engine played illegal move {}", mv),
+                                board.side_to_move,
+                                actions,
+                            ),
+                        }
+                    }
+                    EngineState::Thinking { .. } | EngineState::Stopping => {}
+                    state => self.state = state,
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        if self.state != EngineState::Exited {
+            let _ = writeln!(self.stdin, "quit");
+            let _ = self.stdin.flush();
+        }
+        // Give the engine a moment to quit on its own
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+    }
+}
+
+fn drive_engine(
+    engine: Option<ResMut<UciEngine>>,
+    board: Res<Board>,
+    record: Res<GameRecord>,
+    players: Res<Players>,
+    clock: Option<Res<ChessClock>>,
+    replay: Option<Res<Replay>>,
+    history: Res<History>,
+    mut make_move: EventWriter<MakeMove>,
+    mut actions: EventWriter<GameAction>,
+) {
+    let mut engine = match engine {
+        Some(engine) => engine,
+        None => return,
+    };
+    let engine = &mut *engine;
+
+    loop {
+        let received = engine.lines.lock().unwrap().try_recv();
+        match received {
+            Ok(line) => {
+                debug!("uci < {}", line);
+                engine.handle(
+                    EngineMessage::parse(&line),
+                    &board,
+                    &mut make_move,
+                    &mut actions,
+                );
+            }
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                if engine.state != EngineState::Exited {
+                    error!("engine exited");
+                    engine.state = EngineState::Exited;
+                }
+                break;
+            }
+        }
+    }
+
+    let stale =
+        matches!(&engine.state, EngineState::Thinking { board: searched } if *searched != *board);
+    if stale {
+        engine.send("stop");
+        engine.state = EngineState::Stopping;
+    } else if engine.state == EngineState::Ready
+        && players.get(board.side_to_move) == PlayerKind::External
+        && record.result == GameResult::Ongoing
+        && replay.is_none()
+        && !history.browsing()
+    {
+        if let Err(reason) = engine.prepare(&record.start) {
+            engine.give_up(&reason, board.side_to_move, &mut actions);
+            return;
+        }
+        let moves: Vec<Move> = record.moves.iter().map(|recorded| recorded.mv).collect();
+        engine.send(&uci::position_command(&record.start, &moves));
+        let increment = clock
+            .as_ref()
+            .and_then(|clock| clock.control.fischer_increment());
+        let go = match (clock, increment) {
+            // Let the engine manage its own time when UCI can describe it
+            (Some(clock), Some(increment)) => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                clock.remaining(PieceColor::White).as_millis(),
+                clock.remaining(PieceColor::Black).as_millis(),
+                increment.as_millis(),
+                increment.as_millis()
+            ),
+            // Otherwise a fixed time, at most a small share of what is left
+            (Some(clock), None) => format!(
+                "go movetime {}",
+                engine
+                    .movetime
+                    .min(clock.remaining(board.side_to_move) / 30)
+                    .as_millis()
+            ),
+            (None, _) => format!("go movetime {}", engine.movetime.as_millis()),
+        };
+        engine.send(&go);
+        engine.last_info = None;
+        engine.state = EngineState::Thinking {
+            board: board.clone(),
+        };
+    }
+}
@@ -0,0 +1,47 @@
+//! Defining fairy pieces from pieces files. The definitions are shared by
+//! the whole process, so this is one test in a file of its own.
+
+use bevy_chess::rules::{
+    define_fairy_pieces, fairy_piece, Board, FairyError, MoveMode, MAX_FAIRY_PIECES,
+};
+
+fn pieces_file(letters: &str, betza: &str) -> String {
+    letters
+        .chars()
+        .map(|letter| {
+            format!(
+                "[[piece]]\nname = \"{0}\"\nletter = \"{0}\"\nbetza = \"{1}\"\nvalue = 300\n",
+                letter, betza
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn define_pieces() {
+    let first = "acdefghi";
+    assert_eq!(
+        define_fairy_pieces(&pieces_file(first, "W")).unwrap().len(),
+        first.len()
+    );
+    let board = Board::empty();
+    let wazir = fairy_piece('a').unwrap();
+
+    // Too many with the ones already there: none of them are defined
+    let second = "jlmostuvwxyz";
+    assert!(first.len() + second.len() > MAX_FAIRY_PIECES);
+    assert_eq!(
+        define_fairy_pieces(&pieces_file(second, "F")),
+        Err(FairyError::TooMany)
+    );
+    assert!(second.chars().all(|letter| fairy_piece(letter).is_none()));
+
+    // Redefined, the piece moves differently on boards made from now on
+    define_fairy_pieces(&pieces_file("a", "mR")).unwrap();
+    assert_eq!(fairy_piece('a'), Some(wazir));
+    assert_eq!(board.fairy(wazir).unwrap().betza, "W");
+    let redefined = Board::empty();
+    let rook = redefined.fairy(wazir).unwrap();
+    assert_eq!(rook.betza, "mR");
+    assert!(rook.moves.iter().all(|mv| mv.mode == MoveMode::MoveOnly));
+}
@@ -0,0 +1,249 @@
+//! Two headless ends of a network game talking over localhost.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use bevy_chess::net::{
+    Connection, GameSetup, NetError, NetHost, NetMessage, Role, PROTOCOL_VERSION,
+};
+use bevy_chess::rules::{Board, PieceColor, TimeControl, VariantKind};
+use bevy_chess::uci;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn setup(color: Option<PieceColor>) -> GameSetup {
+    GameSetup {
+        color,
+        start: Board::start(),
+        moves: Vec::new(),
+        control: Some("300+2".parse().unwrap()),
+    }
+}
+
+/// Connects to `host` as `role` and returns both ends, after the host sent
+/// `setup`.
+fn join(host: &NetHost, role: Role, setup: GameSetup) -> (Connection, Connection, GameSetup) {
+    let address = host.local_addr().to_string();
+    let client = thread::spawn(move || Connection::connect(&address, role).unwrap());
+    let (hosted, joined_as) = host.accept().unwrap();
+    assert_eq!(joined_as, role);
+    hosted.send(&NetMessage::Game(Box::new(setup))).unwrap();
+    let (client, received) = client.join().unwrap();
+    (hosted, client, received)
+}
+
+/// A host on a free port and a player connected to it.
+fn connect(setup: GameSetup) -> (Connection, Connection, GameSetup) {
+    let host = NetHost::bind("127.0.0.1:0").unwrap();
+    join(&host, Role::Player, setup)
+}
+
+/// Plays `text` on `board` and sends it with the time left.
+fn play(connection: &Connection, board: &mut Board, text: &str, left: u64) {
+    let mv = uci::parse_move(board, text).unwrap();
+    connection
+        .send(&NetMessage::Move {
+            mv: uci::move_text(board, mv),
+            clock: Some(Duration::from_millis(left)),
+        })
+        .unwrap();
+    board.make_move(mv);
+}
+
+/// Receives a move and plays it on `board`, returning the time it carried.
+fn receive(connection: &Connection, board: &mut Board) -> Option<Duration> {
+    match connection.recv(TIMEOUT).unwrap() {
+        NetMessage::Move { mv, clock } => {
+            let mv = uci::parse_move(board, &mv).unwrap();
+            board.make_move(mv);
+            clock
+        }
+        message => panic!("expected a move, got {:?}", message),
+    }
+}
+
+#[test]
+fn messages_round_trip() {
+    let mut start = Board::chess960(518).unwrap();
+    start.variant = VariantKind::KingOfTheHill;
+    let e4 = uci::parse_move(&start, "e2e4").unwrap();
+    let mut after = start.clone();
+    after.make_move(e4);
+    let e5 = uci::parse_move(&after, "e7e5").unwrap();
+    let messages = [
+        NetMessage::Hello {
+            version: PROTOCOL_VERSION,
+        },
+        NetMessage::Join(Role::Player),
+        NetMessage::Join(Role::Spectator),
+        NetMessage::Game(Box::new(GameSetup {
+            color: Some(PieceColor::Black),
+            start,
+            moves: vec![e4, e5],
+            control: Some(TimeControl::fischer(
+                Duration::from_secs(180),
+                Duration::from_secs(2),
+            )),
+        })),
+        NetMessage::Game(Box::new(GameSetup {
+            color: None,
+            start: Board::start(),
+            moves: Vec::new(),
+            control: None,
+        })),
+        NetMessage::Move {
+            mv: "e7e8q".to_string(),
+            clock: Some(Duration::from_millis(61_250)),
+        },
+        NetMessage::Move {
+            mv: "N@f3".to_string(),
+            clock: None,
+        },
+        NetMessage::Clock {
+            white: Duration::from_millis(1_500),
+            black: Duration::from_millis(299_000),
+        },
+        NetMessage::Resign(PieceColor::White),
+        NetMessage::OfferDraw(PieceColor::Black),
+        NetMessage::AcceptDraw(PieceColor::White),
+        NetMessage::DeclineDraw(PieceColor::Black),
+        NetMessage::Camera([8.5, 0.94, 3.51]),
+        NetMessage::Error("going away".to_string()),
+        NetMessage::Quit,
+    ];
+    for message in messages {
+        let line = message.to_string();
+        assert_eq!(NetMessage::parse(&line), Ok(message), "{}", line);
+    }
+    assert_eq!(
+        NetMessage::parse("takeback please"),
+        Ok(NetMessage::Other("takeback please".to_string()))
+    );
+    assert!(NetMessage::parse("game white fen 8/8/8 w").is_err());
+    assert!(NetMessage::parse("move e2e4 clock soon").is_err());
+    assert!(NetMessage::parse("resign grey").is_err());
+}
+
+#[test]
+fn play_a_game() {
+    let (host, client, received) = connect(setup(Some(PieceColor::Black)));
+    assert_eq!(received, setup(Some(PieceColor::Black)));
+
+    let mut host_board = Board::start();
+    let mut client_board = received.record().start;
+    play(&host, &mut host_board, "e2e4", 300_000);
+    assert_eq!(
+        receive(&client, &mut client_board),
+        Some(Duration::from_millis(300_000))
+    );
+    play(&client, &mut client_board, "e7e5", 298_500);
+    assert_eq!(
+        receive(&host, &mut host_board),
+        Some(Duration::from_millis(298_500))
+    );
+    assert_eq!(host_board, client_board);
+
+    host.send(&NetMessage::Clock {
+        white: Duration::from_millis(299_000),
+        black: Duration::from_millis(298_000),
+    })
+    .unwrap();
+    assert!(matches!(
+        client.recv(TIMEOUT).unwrap(),
+        NetMessage::Clock { .. }
+    ));
+
+    let offer = NetMessage::OfferDraw(PieceColor::Black);
+    client.send(&offer).unwrap();
+    assert_eq!(host.recv(TIMEOUT).unwrap(), offer);
+    let decline = NetMessage::DeclineDraw(PieceColor::White);
+    host.send(&decline).unwrap();
+    assert_eq!(client.recv(TIMEOUT).unwrap(), decline);
+    let resign = NetMessage::Resign(PieceColor::Black);
+    client.send(&resign).unwrap();
+    assert_eq!(host.recv(TIMEOUT).unwrap(), resign);
+
+    drop(client);
+    assert_eq!(host.recv(TIMEOUT).unwrap(), NetMessage::Quit);
+    assert!(matches!(host.recv(TIMEOUT), Err(NetError::Closed)));
+}
+
+#[test]
+fn continue_a_game() {
+    let mut continued = setup(Some(PieceColor::White));
+    let mut board = Board::start();
+    for text in ["d2d4", "d7d5", "c2c4"] {
+        let mv = uci::parse_move(&board, text).unwrap();
+        continued.moves.push(mv);
+        board.make_move(mv);
+    }
+    let (_host, _client, received) = connect(continued.clone());
+    assert_eq!(received, continued);
+    let record = received.record();
+    assert_eq!(record.moves.len(), 3);
+    assert_eq!(record.board_at(3), board);
+    assert_eq!(record.tag("TimeControl"), Some("300+2"));
+}
+
+#[test]
+fn other_version_is_turned_away() {
+    let host = NetHost::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(host.local_addr()).unwrap();
+    writeln!(stream, "chess {}", PROTOCOL_VERSION + 1).unwrap();
+    assert!(matches!(host.accept(), Err(NetError::Version(_))));
+
+    let mut lines = BufReader::new(stream).lines();
+    let hello = format!("chess {}", PROTOCOL_VERSION);
+    assert_eq!(lines.next().unwrap().unwrap(), hello);
+    assert!(lines.next().unwrap().unwrap().starts_with("error "));
+}
+
+#[test]
+fn spectators_follow_the_game() {
+    let host = NetHost::bind("127.0.0.1:0").unwrap();
+    let (to_player, player, _) = join(&host, Role::Player, setup(Some(PieceColor::Black)));
+
+    let mut board = Board::start();
+    let mut player_board = Board::start();
+    play(&to_player, &mut board, "e2e4", 300_000);
+    receive(&player, &mut player_board);
+    play(&player, &mut player_board, "c7c5", 299_000);
+    receive(&to_player, &mut board);
+
+    // Joining later gets the moves so far
+    let mut history = setup(None);
+    history.moves = ["e2e4", "c7c5"]
+        .iter()
+        .scan(Board::start(), |board, text| {
+            let mv = uci::parse_move(board, text)?;
+            board.make_move(mv);
+            Some(mv)
+        })
+        .collect();
+    let (to_spectator, spectator, received) = join(&host, Role::Spectator, history.clone());
+    assert_eq!(received, history);
+    let record = received.record();
+    let mut spectator_board = record.board_at(record.moves.len());
+    assert_eq!(spectator_board, board);
+
+    // And then every move and action as it happens
+    play(&to_spectator, &mut board, "g1f3", 301_000);
+    receive(&spectator, &mut spectator_board);
+    assert_eq!(spectator_board, board);
+    to_spectator
+        .send(&NetMessage::Camera([6.0, 1.2, 0.5]))
+        .unwrap();
+    to_spectator
+        .send(&NetMessage::Resign(PieceColor::Black))
+        .unwrap();
+    assert_eq!(
+        spectator.recv(TIMEOUT).unwrap(),
+        NetMessage::Camera([6.0, 1.2, 0.5])
+    );
+    assert_eq!(
+        spectator.recv(TIMEOUT).unwrap(),
+        NetMessage::Resign(PieceColor::Black)
+    );
+}
@@ -0,0 +1,192 @@
+//! Move generator correctness against the reference perft results from
+//! https://www.chessprogramming.org/Perft_Results
+
+use bevy_chess::rules::{define_fairy_pieces, perft, Board, BoardSize, VariantKind};
+
+fn assert_perft(fen: &str, expected: &[u64]) {
+    let board = Board::from_fen(fen).unwrap();
+    for (depth, &nodes) in expected.iter().enumerate() {
+        let depth = depth as u32 + 1;
+        assert_eq!(perft(&board, depth), nodes, "{} at depth {}", fen, depth);
+    }
+}
+
+#[test]
+fn start_position() {
+    assert_perft(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8_902, 197_281],
+    );
+}
+
+#[test]
+fn kiwipete() {
+    assert_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2_039, 97_862],
+    );
+}
+
+#[test]
+fn position_3() {
+    assert_perft(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2_812, 43_238],
+    );
+}
+
+#[test]
+fn position_4() {
+    assert_perft(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9_467],
+    );
+}
+
+#[test]
+fn position_4_mirrored() {
+    assert_perft(
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[6, 264, 9_467],
+    );
+}
+
+#[test]
+fn position_5() {
+    assert_perft(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1_486, 62_379],
+    );
+}
+
+#[test]
+fn position_6() {
+    assert_perft(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2_079, 89_890],
+    );
+}
+
+// Chess960 positions in Shredder-FEN, from the same site's Chess960 list
+
+#[test]
+fn chess960_position_1() {
+    assert_perft(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12_189],
+    );
+}
+
+#[test]
+fn chess960_position_2() {
+    assert_perft(
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        &[21, 807, 18_002],
+    );
+}
+
+#[test]
+fn chess960_position_3() {
+    assert_perft(
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        &[20, 479, 10_471],
+    );
+}
+
+// Variant start positions, compared with lichess' move generator
+
+fn assert_variant_perft(kind: VariantKind, expected: &[u64]) {
+    let board = kind.setup(Board::start()).unwrap();
+    for (depth, &nodes) in expected.iter().enumerate() {
+        let depth = depth as u32 + 1;
+        assert_eq!(perft(&board, depth), nodes, "{:?} at depth {}", kind, depth);
+    }
+}
+
+#[test]
+fn antichess_start() {
+    assert_variant_perft(VariantKind::Antichess, &[20, 400, 8_067, 153_299]);
+}
+
+#[test]
+fn horde_start() {
+    assert_variant_perft(VariantKind::Horde, &[8, 128, 1_274, 23_310]);
+}
+
+#[test]
+fn crazyhouse_with_pockets() {
+    let board = Board::from_fen("rnb1kbnr/ppp1pppp/8/3q4/8/2N5/PPPP1PPP/R1BQKBNR[Pp] b KQkq - 1 3")
+        .unwrap();
+    assert_eq!(board.variant, VariantKind::Crazyhouse);
+    // 47 moves on the board and a pawn drop on any of 32 empty squares
+    // off the back ranks
+    assert_eq!(perft(&board, 1), 47 + 32);
+}
+
+#[test]
+fn gardner_minichess() {
+    let board = Board::start_with_size(BoardSize::new(5, 5).unwrap()).unwrap();
+    for (depth, &nodes) in [7, 53, 506, 4_775].iter().enumerate() {
+        assert_eq!(perft(&board, depth as u32 + 1), nodes);
+    }
+}
+
+#[test]
+fn capablanca_start() {
+    define_fairy_pieces(include_str!("../assets/pieces/fairy.toml")).unwrap();
+    let board = Board::start_with_size(BoardSize::new(10, 8).unwrap()).unwrap();
+    for (depth, &nodes) in [28, 784, 25_228].iter().enumerate() {
+        assert_eq!(perft(&board, depth as u32 + 1), nodes);
+    }
+}
+
+/// Plays every move `depth` plies deep and takes it back again, checking
+/// the position comes back as it was.
+fn assert_unmake(board: &mut Board, depth: u32) {
+    if depth == 0 {
+        return;
+    }
+    for mv in board.legal_moves() {
+        let before = board.clone();
+        let undo = board.make_reversible_move(mv);
+        assert_unmake(board, depth - 1);
+        board.unmake_move(&undo);
+        assert!(*board == before, "{} after {}", before.to_fen(), mv);
+    }
+}
+
+#[test]
+fn make_unmake_round_trip() {
+    for fen in [
+        // Castling, en passant and promotions
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        // Drops, and captures going to the pocket
+        "rnb1kbnr/ppp1pppp/8/3q4/8/2N5/PPPP1PPP/R1BQKBNR[Pp] b KQkq - 1 3",
+    ] {
+        assert_unmake(&mut Board::from_fen(fen).unwrap(), 3);
+    }
+    for kind in [
+        VariantKind::Atomic,
+        VariantKind::ThreeCheck,
+        VariantKind::Antichess,
+    ] {
+        assert_unmake(&mut kind.setup(Board::start()).unwrap(), 3);
+    }
+}
+
+#[test]
+#[ignore = "slow, run with --release -- --ignored"]
+fn deep() {
+    assert_perft(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8_902, 197_281, 4_865_609],
+    );
+    assert_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2_039, 97_862, 4_085_603],
+    );
+}
@@ -0,0 +1,78 @@
+//! Parsing of UCI lines, sent by GUIs to the uci binary and by external
+//! engines to the game.
+
+use bevy_chess::rules::PieceColor;
+use bevy_chess::uci::{EngineMessage, GoParams, GuiCommand};
+
+#[test]
+fn setoption() {
+    assert_eq!(
+        GuiCommand::parse("setoption name Clear Hash"),
+        Ok(GuiCommand::SetOption {
+            name: "Clear Hash".to_string(),
+            value: None,
+        })
+    );
+    assert_eq!(
+        GuiCommand::parse("setoption name UCI_Chess960 value true"),
+        Ok(GuiCommand::SetOption {
+            name: "UCI_Chess960".to_string(),
+            value: Some("true".to_string()),
+        })
+    );
+    assert!(GuiCommand::parse("setoption value 5 name Hash").is_err());
+    assert!(GuiCommand::parse("setoption value 5").is_err());
+    assert!(GuiCommand::parse("setoption").is_err());
+}
+
+#[test]
+fn go_with_clocks() {
+    let go = GuiCommand::parse("go wtime 60000 btime 55000 winc 1000 binc 1000 movestogo 20");
+    assert_eq!(
+        go,
+        Ok(GuiCommand::Go(GoParams {
+            wtime: Some(60_000),
+            btime: Some(55_000),
+            winc: Some(1_000),
+            binc: Some(1_000),
+            movestogo: Some(20),
+            ..GoParams::default()
+        }))
+    );
+}
+
+#[test]
+fn negative_time_is_no_time() {
+    let params = match GuiCommand::parse("go wtime -250 btime 30000 winc -1") {
+        Ok(GuiCommand::Go(params)) => params,
+        other => panic!("expected go, got {:?}", other),
+    };
+    assert_eq!(params.wtime, Some(0));
+    assert_eq!(params.winc, Some(0));
+    let movetime = params.limits(PieceColor::White).movetime;
+    assert!(matches!(movetime, Some(time) if time.as_millis() <= 1));
+}
+
+#[test]
+fn variant_option() {
+    assert_eq!(
+        EngineMessage::parse(
+            "option name UCI_Variant type combo default chess var chess var atomic var 3check"
+        ),
+        EngineMessage::Option {
+            name: "UCI_Variant".to_string(),
+            vars: vec![
+                "chess".to_string(),
+                "atomic".to_string(),
+                "3check".to_string()
+            ],
+        }
+    );
+    assert_eq!(
+        EngineMessage::parse("option name Clear Hash type button"),
+        EngineMessage::Option {
+            name: "Clear Hash".to_string(),
+            vars: Vec::new(),
+        }
+    );
+}